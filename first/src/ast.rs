@@ -4,14 +4,15 @@ use std::collections::VecDeque;
 
 #[derive(Debug, Clone)]
 pub enum ASTNode {
-    Number(String),
+    Number(u64),
     Identifier(String),
     Multiply(Rc<RefCell<ASTNode>>, Rc<RefCell<ASTNode>>),
     Add(Rc<RefCell<ASTNode>>, Rc<RefCell<ASTNode>>),
-
-    
+    // prefix `-` (two's complement, so the most negative value wraps to itself)
+    Neg(Rc<RefCell<ASTNode>>),
 }
 
+#[allow(dead_code)] // debugging aid, not called by the compiler itself
 pub fn print_level_order(tree_root: &Rc<RefCell<ASTNode>>) {
     // A queue for BFS traversal
     let mut traversal_queue: VecDeque<Rc<RefCell<ASTNode>>> = VecDeque::new();
@@ -36,6 +37,10 @@ pub fn print_level_order(tree_root: &Rc<RefCell<ASTNode>>) {
                     next_level_nodes.push(left.clone());
                     next_level_nodes.push(right.clone());
                 }
+                ASTNode::Neg(operand) => {
+                    print!("neg ");
+                    next_level_nodes.push(operand.clone());
+                }
                 ASTNode::Number(value) => print!("{} ", value),
                 ASTNode::Identifier(name) => print!("{} ", name),
             }
@@ -28,22 +28,37 @@ pub fn generate_expr_code(ast: &Rc<RefCell<ASTNode>>, args: &[String]) -> String
     out
 }
 
+// Where argument `idx` (0-based) arrives: the first six in registers,
+// the rest on the caller's stack above the return address and saved %rbp
+fn arg_location(idx: usize) -> String {
+    match ARG_REGS.get(idx) {
+        Some(reg) => reg.to_string(),
+        None => format!("{}(%rbp)", 16 + 8 * (idx - ARG_REGS.len())),
+    }
+}
+
 // After emit_expr(node): %rax holds the value of `node`
 // Stack pointer is unchanged (every push has a matching pop).
 fn emit_expr(node: &Rc<RefCell<ASTNode>>, out: &mut String, args: &[String]) {
     match &*node.borrow() {
         ASTNode::Number(n) => {
-            // Literal → move immediate into %rax
-            out.push_str(&format!("movq ${}, %rax\n", n));
+            // Literal → move immediate into %rax; `movq $imm` only takes a
+            // sign-extended 32-bit immediate, wider values need `movabsq`
+            let value = *n as i64;
+            if i32::try_from(value).is_ok() {
+                out.push_str(&format!("movq ${}, %rax\n", value));
+            } else {
+                out.push_str(&format!("movabsq ${}, %rax\n", value));
+            }
         }
 
         ASTNode::Identifier(name) => {
-            // Map identifier to its argument register (first-seen order decided by caller)
+            // Map identifier to its argument (first-seen order decided by caller)
             let idx = args
                 .iter()
                 .position(|a| a == name)
                 .expect("identifier not found in arg list");
-            out.push_str(&format!("movq {}, %rax\n", ARG_REGS[idx]));
+            out.push_str(&format!("movq {}, %rax\n", arg_location(idx)));
         }
 
         ASTNode::Add(l, r) => {
@@ -71,5 +86,10 @@ fn emit_expr(node: &Rc<RefCell<ASTNode>>, out: &mut String, args: &[String]) {
             out.push_str("popq %rbx\n");
             out.push_str("imulq %rbx, %rax\n");
         }
+
+        ASTNode::Neg(operand) => {
+            emit_expr(operand, out, args);
+            out.push_str("negq %rax\n");
+        }
     }
 }
\ No newline at end of file
@@ -1,3 +1,5 @@
+mod span;
+mod diagnostics;
 mod ast;
 mod scanner;
 mod parser;
@@ -5,12 +7,15 @@ mod codegen;
 
 use std::env;
 use std::fs;
+use std::process;
+use std::collections::HashSet;
 
 
 use crate::parser::Parser;
 use crate::codegen::generate_expr_code;
 use crate::ast::ASTNode;
-use crate::scanner::scan_source;
+use crate::scanner::{scan_source, SpannedToken};
+use crate::diagnostics::{report, Diagnostic, ErrorFormat, E_INVALID_CHARACTER, E_UNTERMINATED_COMMENT};
 
 use std::cell::RefCell;
 use std::rc::Rc;
@@ -38,28 +43,73 @@ fn collect_identifiers_in_order(
             collect_identifiers_in_order(l, out, seen);
             collect_identifiers_in_order(r, out, seen);
         }
+
+        Neg(operand) => collect_identifiers_in_order(operand, out, seen),
     }
 }
 
+// Where the token after each skipped character or unterminated comment
+// starts. A syntax error there is the scanner's error seen again, so
+// only the scanner's is reported.
+fn cascade_starts(tokens: &[SpannedToken], lexical: &[Diagnostic]) -> HashSet<usize> {
+    lexical
+        .iter()
+        .filter(|d| d.code == E_INVALID_CHARACTER || d.code == E_UNTERMINATED_COMMENT)
+        .filter_map(|d| tokens.iter().find(|t| t.span.start >= d.span.end))
+        .map(|t| t.span.start)
+        .collect()
+}
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("error: {}", message);
+    eprintln!("Usage: rucompiler-x86-first [--error-format=human|json] <file.exp>");
+    process::exit(2);
+}
+
 fn main() {
-    let args: Vec<String> = env::args().collect();
-    if args.len() != 2 {
-        eprintln!("Usage: rucompiler-x86-first <file.exp>");
-        return;
+    let mut input_file: Option<String> = None;
+    let mut error_format = ErrorFormat::Human;
+
+    for arg in env::args().skip(1) {
+        if let Some(value) = arg.strip_prefix("--error-format=") {
+            match ErrorFormat::from_flag(value) {
+                Some(format) => error_format = format,
+                None => usage_error(&format!("unknown error format `{}` (expected `human` or `json`)", value)),
+            }
+        } else if arg.starts_with("--") || input_file.is_some() {
+            usage_error(&format!("unexpected argument `{}`", arg));
+        } else {
+            input_file = Some(arg);
+        }
     }
 
-    let input_file = &args[1];
+    let input_file = match input_file {
+        Some(file) => file,
+        None => usage_error("missing input file"),
+    };
+    let input_file = &input_file;
     let output_file = input_file.replace(".exp", ".s");
 
     let source = fs::read_to_string(input_file)
         .expect("Failed to read input file");
 
-    // 1. Tokenize using your real scanner.
-    let tokens = scan_source(&source);
+    // 1. Tokenize using your real scanner; lexical errors are reported
+    //    together with the syntax errors in the tokens that remain
+    let (tokens, mut diagnostics) = scan_source(&source);
+    let cascades = cascade_starts(&tokens, &diagnostics);
 
     // 2. Parse
     let mut parser = Parser::new(tokens);
-    let ast = parser.parse_expression();
+    let ast = match parser.parse_program() {
+        Ok(ast) if diagnostics.is_empty() => ast,
+        result => {
+            let syntax = result.err().unwrap_or_default();
+            diagnostics.extend(syntax.into_iter().filter(|d| !cascades.contains(&d.span.start)));
+            diagnostics.sort_by_key(|d| d.span.start);
+            report(&diagnostics, input_file, &source, error_format);
+            process::exit(1);
+        }
+    };
 
     // 3. Collect identifiers
     let mut ids = Vec::new();
@@ -1,5 +1,7 @@
-use crate::scanner::Token;
+use crate::scanner::{SpannedToken, Token};
 use crate::ast::ASTNode;
+use crate::diagnostics::{Diagnostic, E_LITERAL_TOO_LARGE, E_TRAILING_INPUT, E_UNEXPECTED_TOKEN};
+use crate::span::Span;
 use std::cell::RefCell;
 use std::rc::Rc;
 
@@ -10,20 +12,24 @@ use std::rc::Rc;
 /// EXPR'    → + TERM EXPR' | ε
 /// TERM     → FACTOR TERM'
 /// TERM'    → * FACTOR TERM' | ε
-/// FACTOR   → IDENTIFIER | NUMBER | ( EXPR )
+/// FACTOR   → - FACTOR | + FACTOR | IDENTIFIER | NUMBER | ( EXPR )
 ///
 /// This parser constructs an Abstract Syntax Tree (AST)
 /// where operators (+, *) become internal nodes and
 /// identifiers/numbers become leaf nodes.
-
+///
+/// Each production returns `Err` with a diagnostic at the first token it
+/// cannot accept; `parse_program` collects those for the caller.
 pub struct Parser {
-    token_stream: Vec<Token>,
+    token_stream: Vec<SpannedToken>,
     current_pos: usize,
 }
 
+type ParseResult<T> = Result<T, Box<Diagnostic>>;
+
 impl Parser {
     /// Create a new parser from a token list.
-    pub fn new(tokens: Vec<Token>) -> Self {
+    pub fn new(tokens: Vec<SpannedToken>) -> Self {
         Parser {
             token_stream: tokens,
             current_pos: 0,
@@ -34,9 +40,29 @@ impl Parser {
     fn peek(&self) -> &Token {
         self.token_stream
             .get(self.current_pos)
+            .map(|t| &t.token)
             .unwrap_or(&Token::EndOfFile)
     }
 
+    /// Span of the current token (the EOF token once the stream is exhausted).
+    fn peek_span(&self) -> Span {
+        self.token_stream
+            .get(self.current_pos)
+            .or(self.token_stream.last())
+            .map(|t| t.span)
+            .unwrap_or_default()
+    }
+
+    /// Build an "expected X, found Y" error located at the current token.
+    fn unexpected(&self, expected: &str) -> Diagnostic {
+        Diagnostic::error(
+            E_UNEXPECTED_TOKEN,
+            format!("expected {}, found {}", expected, self.peek()),
+            self.peek_span(),
+        )
+        .with_label(format!("expected {}", expected))
+    }
+
     /// Move to the next token in the stream.
     fn consume(&mut self) {
         if self.current_pos < self.token_stream.len() {
@@ -44,12 +70,31 @@ impl Parser {
         }
     }
 
+    // ============================================================
+    // PROGRAM → EXPR EOF
+    // ============================================================
+    /// Parse a whole `.exp` file: exactly one expression and nothing after it.
+    pub fn parse_program(&mut self) -> Result<Rc<RefCell<ASTNode>>, Vec<Diagnostic>> {
+        let expr = self.parse_expression().map_err(|d| vec![*d])?;
+
+        match self.peek() {
+            Token::EndOfFile => Ok(expr),
+            trailing => Err(vec![Diagnostic::error(
+                E_TRAILING_INPUT,
+                format!("expected operator or end of file, found {}", trailing),
+                self.peek_span(),
+            )
+            .with_label("expected operator or end of file")
+            .with_note("an .exp file holds a single expression")]),
+        }
+    }
+
     // ============================================================
     // EXPR → TERM EXPR'
     // ============================================================
     /// Parse an expression (handles '+' at the top level).
-    pub fn parse_expression(&mut self) -> Rc<RefCell<ASTNode>> {
-        let term_node = self.parse_term();
+    pub fn parse_expression(&mut self) -> ParseResult<Rc<RefCell<ASTNode>>> {
+        let term_node = self.parse_term()?;
         self.parse_expression_tail(term_node)
     }
 
@@ -57,18 +102,18 @@ impl Parser {
     // EXPR' → + TERM EXPR' | ε
     // ============================================================
     /// Continue parsing if a '+' is found after a term.
-    fn parse_expression_tail(&mut self, accumulated: Rc<RefCell<ASTNode>>) -> Rc<RefCell<ASTNode>> {
+    fn parse_expression_tail(&mut self, accumulated: Rc<RefCell<ASTNode>>) -> ParseResult<Rc<RefCell<ASTNode>>> {
         match self.peek() {
             Token::Plus => {
                 // consume '+'
                 self.consume();
-                let next_term = self.parse_term();
+                let next_term = self.parse_term()?;
                 // combine left and right with '+'
                 let combined_expr = Rc::new(RefCell::new(ASTNode::Add(accumulated, next_term)));
                 // continue checking for more '+'
                 self.parse_expression_tail(combined_expr)
             }
-            _ => accumulated, // no '+' → epsilon transition
+            _ => Ok(accumulated), // no '+' → epsilon transition
         }
     }
 
@@ -76,8 +121,8 @@ impl Parser {
     // TERM → FACTOR TERM'
     // ============================================================
     /// Parse a term (handles '*' operations).
-    fn parse_term(&mut self) -> Rc<RefCell<ASTNode>> {
-        let factor_node = self.parse_factor();
+    fn parse_term(&mut self) -> ParseResult<Rc<RefCell<ASTNode>>> {
+        let factor_node = self.parse_factor()?;
         self.parse_term_tail(factor_node)
     }
 
@@ -85,59 +130,99 @@ impl Parser {
     // TERM' → * FACTOR TERM' | ε
     // ============================================================
     /// Continue parsing if a '*' follows a factor.
-    fn parse_term_tail(&mut self, accumulated: Rc<RefCell<ASTNode>>) -> Rc<RefCell<ASTNode>> {
+    fn parse_term_tail(&mut self, accumulated: Rc<RefCell<ASTNode>>) -> ParseResult<Rc<RefCell<ASTNode>>> {
         match self.peek() {
             Token::Multiply => {
                 // consume '*'
                 self.consume();
-                let next_factor = self.parse_factor();
+                let next_factor = self.parse_factor()?;
                 // combine with multiplication
                 let combined_term = Rc::new(RefCell::new(ASTNode::Multiply(accumulated, next_factor)));
                 // recursively continue
                 self.parse_term_tail(combined_term)
             }
-            _ => accumulated, // no '*' → epsilon transition
+            _ => Ok(accumulated), // no '*' → epsilon transition
         }
     }
 
     // ============================================================
-    // FACTOR → IDENTIFIER | NUMBER | ( EXPR )
+    // FACTOR → - FACTOR | + FACTOR | IDENTIFIER | NUMBER | ( EXPR )
     // ============================================================
-    /// Parse a factor (base unit: identifier, number, or parenthesized expression).
-    fn parse_factor(&mut self) -> Rc<RefCell<ASTNode>> {
+    /// Parse a factor (base unit: identifier, number, or parenthesized expression,
+    /// optionally preceded by a sign).
+    fn parse_factor(&mut self) -> ParseResult<Rc<RefCell<ASTNode>>> {
         match self.peek() {
+            // '-' directly before a literal → a negative literal. Any literal
+            // up to u64::MAX is kept as its bit pattern (9223372036854775808
+            // alone is i64::MIN), but a negated one must be at least i64::MIN
+            Token::Minus => {
+                let minus_span = self.peek_span();
+                self.consume(); // consume '-'
+                if let Token::Number(value) = *self.peek() {
+                    let literal_span = self.peek_span();
+                    self.consume();
+                    let value = negate_literal(value, minus_span, literal_span)?;
+                    return Ok(Rc::new(RefCell::new(ASTNode::Number(value))));
+                }
+                let operand = self.parse_factor()?;
+                Ok(Rc::new(RefCell::new(ASTNode::Neg(operand))))
+            }
+
+            // '+' → no effect on the value
+            Token::Plus => {
+                self.consume(); // consume '+'
+                self.parse_factor()
+            }
+
             // If it's a number → create a numeric node
             Token::Number(num_value) => {
-                let number_node = Rc::new(RefCell::new(ASTNode::Number(num_value.clone())));
+                let number_node = Rc::new(RefCell::new(ASTNode::Number(*num_value)));
                 self.consume();
-                number_node
+                Ok(number_node)
             }
 
             // If it's an identifier → create a variable node
             Token::Identifier(var_name) => {
                 let ident_node = Rc::new(RefCell::new(ASTNode::Identifier(var_name.clone())));
                 self.consume();
-                ident_node
+                Ok(ident_node)
             }
 
             // If it's '(' → parse a full expression inside the parentheses
             Token::LeftParen => {
+                let open_span = self.peek_span();
                 self.consume(); // consume '('
-                let inner_expr = self.parse_expression();
+                let inner_expr = self.parse_expression()?;
 
                 // expect closing ')'
                 if let Token::RightParen = self.peek() {
                     self.consume(); // consume ')'
-                    inner_expr
+                    Ok(inner_expr)
                 } else {
-                    panic!("Syntax error: expected ')' after expression");
+                    let mut d = self.unexpected("`)`").with_secondary(open_span, "`(` opened here");
+                    if let Token::EndOfFile = self.peek() {
+                        let at = self.peek_span();
+                        d = d.with_suggestion("insert `)`", at, ")");
+                    }
+                    Err(d.into())
                 }
             }
 
             // Any other token → invalid factor
-            unexpected_token => {
-                panic!("Unexpected token in factor: {:?}", unexpected_token);
-            }
+            _ => Err(self.unexpected("expression").into()),
         }
     }
 }
+
+/// Bit pattern of `-value`, or an error if `-value` is below the smallest
+/// signed 64-bit integer.
+fn negate_literal(value: u64, minus_span: Span, literal_span: Span) -> ParseResult<u64> {
+    if value > i64::MIN.unsigned_abs() {
+        let span = Span::new(minus_span.start, literal_span.end, minus_span.line, minus_span.column);
+        return Err(Diagnostic::error(E_LITERAL_TOO_LARGE, format!("integer literal `-{}` is too small", value), span)
+            .with_label("does not fit in 64 bits")
+            .with_note(format!("the smallest literal is {}", i64::MIN))
+            .into());
+    }
+    Ok(value.wrapping_neg())
+}
@@ -1,20 +1,52 @@
+use crate::diagnostics::{
+    Diagnostic, E_INVALID_CHARACTER, E_LITERAL_TOO_LARGE, E_MALFORMED_LITERAL, E_UNTERMINATED_COMMENT,
+};
+use crate::span::Span;
+use std::fmt;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Token {
     Identifier(String),
-    Number(String),
+    Number(u64),
     Plus,      
+    Minus,     // prefix only: the expression language has no subtraction
     Multiply, 
     LeftParen,  
     RightParen,  
-    Invalid,     
     EndOfFile,   
 }
 
+/// How a token is named in diagnostics
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::Identifier(name) => write!(f, "identifier `{}`", name),
+            Token::Number(value) => write!(f, "number `{}`", value),
+            Token::Plus => f.write_str("`+`"),
+            Token::Minus => f.write_str("`-`"),
+            Token::Multiply => f.write_str("`*`"),
+            Token::LeftParen => f.write_str("`(`"),
+            Token::RightParen => f.write_str("`)`"),
+            Token::EndOfFile => f.write_str("end of file"),
+        }
+    }
+}
+
+/// A token together with the source region it was read from
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpannedToken {
+    pub token: Token,
+    pub span: Span,
+}
 
 
 pub struct Scanner {
     position: usize,        // current reading index
     input_chars: Vec<char>, // input stored as character vector
+    offset: usize,          // byte offset of `position`
+    line: usize,            // 1-based line of `position`
+    column: usize,          // 1-based column of `position`
+    diagnostics: Vec<Diagnostic>, // lexical errors found so far
 }
 
 impl Scanner {
@@ -23,6 +55,10 @@ impl Scanner {
         Scanner {
             position: 0,
             input_chars: source.chars().collect(),
+            offset: 0,
+            line: 1,
+            column: 1,
+            diagnostics: Vec::new(),
         }
     }
 
@@ -31,26 +67,101 @@ impl Scanner {
         self.input_chars.get(self.position).copied()
     }
 
-    /// Advances the scanner by one character and returns it
+    /// Returns the character one past the reading index (two-char lookahead)
+    fn char_after_current(&self) -> Option<char> {
+        self.input_chars.get(self.position + 1).copied()
+    }
+
+    /// Advances the scanner by one character and returns it,
+    /// keeping the byte offset, line and column in step
     fn advance(&mut self) -> Option<char> {
         let current = self.current_char();
-        if current.is_some() {
+        if let Some(ch) = current {
             self.position += 1;
+            self.offset += ch.len_utf8();
+            if ch == '\n' {
+                self.line += 1;
+                self.column = 1;
+            } else {
+                self.column += 1;
+            }
         }
         current
     }
 
-    /// Skips over whitespace characters (spaces, tabs, newlines)
-    fn skip_whitespace(&mut self) {
-        while let Some(ch) = self.current_char() {
-            if ch.is_whitespace() {
-                self.advance();
-            } else {
-                break;
+    /// Zero-width span at the current reading position
+    fn here(&self) -> Span {
+        Span::new(self.offset, self.offset, self.line, self.column)
+    }
+
+    /// Span from `start` up to the current reading position
+    fn span_from(&self, start: Span) -> Span {
+        Span::new(start.start, self.offset, start.line, start.column)
+    }
+
+    /// Skips over whitespace characters (spaces, tabs, newlines),
+    /// `// line` comments and nestable `/* block */` comments
+    fn skip_whitespace_and_comments(&mut self) {
+        loop {
+            match (self.current_char(), self.char_after_current()) {
+                (Some(ch), _) if ch.is_whitespace() => {
+                    self.advance();
+                }
+                (Some('/'), Some('/')) => {
+                    while let Some(ch) = self.current_char() {
+                        if ch == '\n' {
+                            break;
+                        }
+                        self.advance();
+                    }
+                }
+                (Some('/'), Some('*')) => self.skip_block_comment(),
+                _ => break,
             }
         }
     }
 
+    /// Called at `/*`: consumes through the matching `*/`, counting nested
+    /// pairs, and reports the outermost comment if the file ends first
+    fn skip_block_comment(&mut self) {
+        let mut open: Vec<Span> = Vec::new();
+
+        loop {
+            match (self.current_char(), self.char_after_current()) {
+                (Some('/'), Some('*')) => {
+                    let start = self.here();
+                    self.advance();
+                    self.advance();
+                    open.push(self.span_from(start));
+                }
+                (Some('*'), Some('/')) => {
+                    self.advance();
+                    self.advance();
+                    open.pop();
+                    if open.is_empty() {
+                        return;
+                    }
+                }
+                (Some(_), _) => {
+                    self.advance();
+                }
+                (None, _) => break,
+            }
+        }
+
+        let closers = "*/".repeat(open.len());
+        let mut d = Diagnostic::error(E_UNTERMINATED_COMMENT, "unterminated block comment", open[0])
+            .with_label("comment starts here")
+            .with_suggestion(format!("insert `{}` at the end of the file", closers), self.here(), closers);
+        for inner in &open[1..] {
+            d = d.with_secondary(*inner, "nested comment, also unterminated");
+        }
+        if open.len() > 1 {
+            d = d.with_note("block comments nest: every `/*` needs its own `*/`");
+        }
+        self.diagnostics.push(d);
+    }
+
     /// Helper function: keeps collecting characters while the
     /// given predicate returns true (e.g. `is_digit` or `is_alpha`)
     fn collect_while<F: Fn(char) -> bool>(&mut self, first: char, cond: F) -> String {
@@ -66,21 +177,40 @@ impl Scanner {
     }
 
     /// Reads and returns the next token from the input stream.
-    fn next_token(&mut self) -> Option<Token> {
-        self.skip_whitespace(); // ignore spaces
+    /// Characters that start no token are reported and skipped.
+    fn next_token(&mut self) -> Option<SpannedToken> {
+        loop {
+            self.skip_whitespace_and_comments(); // ignore spaces and comments
 
-        let ch = self.advance()?; // move to next char, or None if EOF
+            let start = self.here();
+            let ch = self.advance()?; // move to next char, or None if EOF
 
+            if let Some(token) = self.scan_token(ch, start) {
+                return Some(SpannedToken { token, span: self.span_from(start) });
+            }
+        }
+    }
+
+    /// Builds the token that starts with `ch` (already consumed).
+    /// Returns None after reporting `ch` if it cannot start a token.
+    fn scan_token(&mut self, ch: char, start: Span) -> Option<Token> {
         let token = match ch {
             '+' => Token::Plus,
+            '-' => Token::Minus,
             '*' => Token::Multiply,
             '(' => Token::LeftParen,
             ')' => Token::RightParen,
 
-            // Begin numeric literal
+            // Begin numeric literal (decimal, or 0x / 0b / 0o prefixed)
             d if d.is_ascii_digit() => {
-                let digits = self.collect_while(d, |c| c.is_ascii_digit());
-                Token::Number(digits)
+                let text = self.collect_while(d, |c| c.is_ascii_alphanumeric() || c == '_');
+                match parse_integer_literal(&text) {
+                    Ok(value) => Token::Number(value),
+                    Err(problem) => {
+                        self.diagnostics.push(problem.into_diagnostic(&text, self.span_from(start)));
+                        Token::Number(0)
+                    }
+                }
             }
 
             // Begin identifier
@@ -90,14 +220,25 @@ impl Scanner {
             }
 
             // Anything else is not valid in this grammar
-            _ => Token::Invalid,
+            other => {
+                self.diagnostics.push(
+                    Diagnostic::error(
+                        E_INVALID_CHARACTER,
+                        format!("invalid character `{}`", other.escape_debug()),
+                        self.span_from(start),
+                    )
+                    .with_label("not part of any token"),
+                );
+                return None;
+            }
         };
 
         Some(token)
     }
 
-    /// Produces a complete list of tokens from the input string.
-    pub fn tokenize(&mut self) -> Vec<Token> {
+    /// Produces a complete list of tokens from the input string,
+    /// along with every lexical error found in it.
+    pub fn tokenize(&mut self) -> (Vec<SpannedToken>, Vec<Diagnostic>) {
         let mut tokens = Vec::new();
 
         // Continue reading tokens until exhaustion
@@ -106,13 +247,99 @@ impl Scanner {
         }
 
         // Append EOF marker for parser convenience
-        tokens.push(Token::EndOfFile);
-        tokens
+        tokens.push(SpannedToken { token: Token::EndOfFile, span: self.here() });
+
+        (tokens, std::mem::take(&mut self.diagnostics))
     }
 }
 
 
-pub fn scan_source(source: &str) -> Vec<Token> {
+// ===============================================================
+// Integer literals
+// An optional radix prefix (0x, 0b, 0o) followed by digits of that radix;
+// a single `_` may separate two digits, but cannot follow the prefix,
+// another `_` or end the literal. The value must
+// fit in 64 bits; whether it is read as signed is up to the operations
+// applied to it.
+// ===============================================================
+enum LiteralProblem {
+    NoDigits(&'static str),
+    InvalidDigit(char, &'static str),
+    TooLarge(&'static str),
+    MisplacedUnderscore(&'static str, &'static str),
+}
+
+impl LiteralProblem {
+    fn into_diagnostic(self, text: &str, span: Span) -> Diagnostic {
+        match self {
+            LiteralProblem::NoDigits(kind) => {
+                Diagnostic::error(E_MALFORMED_LITERAL, format!("{} literal `{}` has no digits", kind, text), span)
+                    .with_label("expected at least one digit after the prefix")
+            }
+            LiteralProblem::InvalidDigit(c, kind) => {
+                Diagnostic::error(E_MALFORMED_LITERAL, format!("invalid digit `{}` in {} literal", c, kind), span)
+                    .with_label(format!("`{}` is not a {} digit", c, kind))
+            }
+            LiteralProblem::MisplacedUnderscore(kind, reason) => {
+                Diagnostic::error(E_MALFORMED_LITERAL, format!("misplaced `_` in {} literal `{}`", kind, text), span)
+                    .with_label(reason)
+                    .with_note("`_` may only separate two digits")
+            }
+            LiteralProblem::TooLarge(kind) => {
+                Diagnostic::error(E_LITERAL_TOO_LARGE, format!("{} literal `{}` is too large", kind, text), span)
+                    .with_label("does not fit in 64 bits")
+                    .with_note(format!("the largest literal is {} (0x{:x})", u64::MAX, u64::MAX))
+            }
+        }
+    }
+}
+
+/// Value of a literal such as `42`, `1_000`, `0xff`, `0b1010` or `0o17`.
+fn parse_integer_literal(text: &str) -> Result<u64, LiteralProblem> {
+    let lower = text.get(..2).map(|p| p.to_ascii_lowercase());
+    let (radix, kind, digits) = match lower.as_deref() {
+        Some("0x") => (16, "hexadecimal", &text[2..]),
+        Some("0b") => (2, "binary", &text[2..]),
+        Some("0o") => (8, "octal", &text[2..]),
+        _ => (10, "decimal", text),
+    };
+
+    let mut value: u64 = 0;
+    let mut seen_digit = false;
+    let mut after_underscore = false;
+    for c in digits.chars() {
+        if c == '_' {
+            if !seen_digit {
+                return Err(LiteralProblem::MisplacedUnderscore(kind, "`_` cannot follow the prefix"));
+            }
+            if after_underscore {
+                return Err(LiteralProblem::MisplacedUnderscore(kind, "`_` cannot follow another `_`"));
+            }
+            after_underscore = true;
+            continue;
+        }
+        after_underscore = false;
+        let digit = c.to_digit(radix).ok_or(LiteralProblem::InvalidDigit(c, kind))?;
+        value = value
+            .checked_mul(radix as u64)
+            .and_then(|v| v.checked_add(digit as u64))
+            .ok_or(LiteralProblem::TooLarge(kind))?;
+        seen_digit = true;
+    }
+
+    if !seen_digit {
+        return Err(LiteralProblem::NoDigits(kind));
+    }
+    if after_underscore {
+        return Err(LiteralProblem::MisplacedUnderscore(kind, "`_` cannot end a literal"));
+    }
+    Ok(value)
+}
+
+/// Every token of `source` plus every lexical error in it. Invalid
+/// characters are skipped and malformed literals read as 0, so the
+/// tokens can still be parsed for further errors.
+pub fn scan_source(source: &str) -> (Vec<SpannedToken>, Vec<Diagnostic>) {
     let mut lexer = Scanner::new(source.to_string());
     lexer.tokenize()
 }
@@ -0,0 +1,17 @@
+/// A region of the source file.
+///
+/// `start`/`end` are byte offsets (end is exclusive), `line`/`column`
+/// are 1-based and describe where the region begins.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Span {
+    pub start: usize,
+    pub end: usize,
+    pub line: usize,
+    pub column: usize,
+}
+
+impl Span {
+    pub fn new(start: usize, end: usize, line: usize, column: usize) -> Self {
+        Span { start, end, line, column }
+    }
+}
@@ -2,14 +2,64 @@ use std::cell::RefCell;
 use std::rc::Rc;
 use std::collections::VecDeque;
 
+use crate::span::Span;
+
+// Every node carries the span of the source text it was parsed from
+// as its last field.
 #[derive(Debug, Clone)]
 pub enum ASTNode {
-    Number(String),
-    Identifier(String),
-    Multiply(Rc<RefCell<ASTNode>>, Rc<RefCell<ASTNode>>),
-    Add(Rc<RefCell<ASTNode>>, Rc<RefCell<ASTNode>>),
-    Sub(Rc<RefCell<ASTNode>>, Rc<RefCell<ASTNode>>),
-    
+    // literal value as a 64-bit pattern
+    Number(u64, Span),
+    Identifier(String, Span),
+    Multiply(Rc<RefCell<ASTNode>>, Rc<RefCell<ASTNode>>, Span),
+    Add(Rc<RefCell<ASTNode>>, Rc<RefCell<ASTNode>>, Span),
+    Sub(Rc<RefCell<ASTNode>>, Rc<RefCell<ASTNode>>, Span),
+    // signed, truncating toward zero like C
+    Divide(Rc<RefCell<ASTNode>>, Rc<RefCell<ASTNode>>, Span),
+    Modulo(Rc<RefCell<ASTNode>>, Rc<RefCell<ASTNode>>, Span),
+    // prefix `-`; wraps, so -i64::MIN == i64::MIN
+    Neg(Rc<RefCell<ASTNode>>, Span),
+    // bitwise; shift counts are taken modulo 64, as the hardware does
+    BitAnd(Rc<RefCell<ASTNode>>, Rc<RefCell<ASTNode>>, Span),
+    BitOr(Rc<RefCell<ASTNode>>, Rc<RefCell<ASTNode>>, Span),
+    BitXor(Rc<RefCell<ASTNode>>, Rc<RefCell<ASTNode>>, Span),
+    BitNot(Rc<RefCell<ASTNode>>, Span),
+    ShiftLeft(Rc<RefCell<ASTNode>>, Rc<RefCell<ASTNode>>, Span),
+    ShiftRight(Rc<RefCell<ASTNode>>, Rc<RefCell<ASTNode>>, Span),
+    ShiftRightLogical(Rc<RefCell<ASTNode>>, Rc<RefCell<ASTNode>>, Span),
+    // a condition used as a value: 1 if it holds, 0 otherwise
+    Condition(Box<BooleanExpression>, Span),
+    // name(args...); the arguments are evaluated left to right
+    Call(String, Vec<Rc<RefCell<ASTNode>>>, Span),
+    // name[index]: an element of a local array
+    Index(String, Rc<RefCell<ASTNode>>, Span),
+}
+
+impl ASTNode {
+    pub fn span(&self) -> Span {
+        use ASTNode::*;
+
+        match self {
+            Number(_, span)
+            | Identifier(_, span)
+            | Neg(_, span)
+            | BitNot(_, span)
+            | BitAnd(_, _, span)
+            | BitOr(_, _, span)
+            | BitXor(_, _, span)
+            | ShiftLeft(_, _, span)
+            | ShiftRight(_, _, span)
+            | ShiftRightLogical(_, _, span)
+            | Condition(_, span)
+            | Call(_, _, span)
+            | Index(_, _, span)
+            | Multiply(_, _, span)
+            | Add(_, _, span)
+            | Sub(_, _, span)
+            | Divide(_, _, span)
+            | Modulo(_, _, span) => *span,
+        }
+    }
 }
 
 // START *********: Control Flow Extension :********* ///
@@ -17,45 +67,159 @@ pub enum ASTNode {
 #[derive(Debug, Clone)]
 pub enum BooleanExpression {
 
-    True, False,
+    True(Span), False(Span),
+
+    CompareLessThan(Rc<RefCell<ASTNode>>, Rc<RefCell<ASTNode>>, Span),
+    CompareLessThanEqualTo(Rc<RefCell<ASTNode>>, Rc<RefCell<ASTNode>>, Span),
+    CompareGreaterThan(Rc<RefCell<ASTNode>>, Rc<RefCell<ASTNode>>, Span),
+    CompareGreaterThanEqualTo(Rc<RefCell<ASTNode>>, Rc<RefCell<ASTNode>>, Span),
+    CompareEqualTo(Rc<RefCell<ASTNode>>, Rc<RefCell<ASTNode>>, Span),
+    CompareNotEqualTo(Rc<RefCell<ASTNode>>, Rc<RefCell<ASTNode>>, Span),
+
+    // short-circuit: the right side only runs when it decides the result
+    And(Box<BooleanExpression>, Box<BooleanExpression>, Span),
+    Or(Box<BooleanExpression>, Box<BooleanExpression>, Span),
+    Not(Box<BooleanExpression>, Span),
+}
+
+impl BooleanExpression {
+    pub fn span(&self) -> Span {
+        use BooleanExpression::*;
 
-    CompareLessThan(Rc<RefCell<ASTNode>>, Rc<RefCell<ASTNode>>),
-    CompareLessThanEqualTo(Rc<RefCell<ASTNode>>, Rc<RefCell<ASTNode>>),
-    CompareGreaterThan(Rc<RefCell<ASTNode>>, Rc<RefCell<ASTNode>>),
-    CompareGreaterThanEqualTo(Rc<RefCell<ASTNode>>, Rc<RefCell<ASTNode>>),
-    CompareEqualTo(Rc<RefCell<ASTNode>>, Rc<RefCell<ASTNode>>),
+        match self {
+            True(span) | False(span) => *span,
+            CompareLessThan(_, _, span)
+            | CompareLessThanEqualTo(_, _, span)
+            | CompareGreaterThan(_, _, span)
+            | CompareGreaterThanEqualTo(_, _, span)
+            | CompareEqualTo(_, _, span)
+            | CompareNotEqualTo(_, _, span) => *span,
+            And(_, _, span) | Or(_, _, span) | Not(_, span) => *span,
+        }
+    }
 }
 
 
 #[derive(Debug, Clone)]
 pub enum Statement{
+    // `compound` for `x OP= e`, `x++` and `x--`, stored as `x = x OP e`;
+    // their expression already reads `name` at `name_span`
     Assign { 
-        name: String, expression: Rc<RefCell<ASTNode>>
+        name: String, name_span: Span, expression: Rc<RefCell<ASTNode>>, compound: bool, span: Span
+    },
+
+    // name[index] = expression;  -- the index is evaluated first
+    AssignElement {
+        name: String, name_span: Span, index: Rc<RefCell<ASTNode>>, expression: Rc<RefCell<ASTNode>>, span: Span
     },
 
     If {
-        condition: BooleanExpression, then_statements: Vec<Statement>, else_statements: Vec<Statement>
+        condition: BooleanExpression, then_statements: Vec<Statement>, else_statements: Vec<Statement>, span: Span
     },
 
     While {
-        condition: BooleanExpression, body: Vec<Statement>
+        condition: BooleanExpression, body: Vec<Statement>, span: Span
+    },
+
+    // for (init; condition; step) { body } -- init and step are
+    // optional assignments; `continue` jumps to the step
+    For {
+        init: Option<Box<Statement>>, condition: BooleanExpression, step: Option<Box<Statement>>,
+        body: Vec<Statement>, span: Span
+    },
+
+    // do { body } while condition;  -- the body runs at least once
+    DoWhile {
+        body: Vec<Statement>, condition: BooleanExpression, span: Span
     },
+
+    // leave / restart the innermost enclosing loop
+    Break { span: Span },
+    Continue { span: Span },
+
+    // return EXPR;  -- allowed anywhere; leaves the function
+    Return { expression: Rc<RefCell<ASTNode>>, span: Span },
+
+    // name(args...);  -- a call made for its effect; the result is dropped
+    Call { expression: Rc<RefCell<ASTNode>>, span: Span },
+}
+
+impl Statement {
+    #[allow(dead_code)]
+    pub fn span(&self) -> Span {
+        match self {
+            Statement::Assign { span, .. }
+            | Statement::AssignElement { span, .. }
+            | Statement::If { span, .. }
+            | Statement::While { span, .. }
+            | Statement::For { span, .. }
+            | Statement::DoWhile { span, .. }
+            | Statement::Break { span }
+            | Statement::Continue { span }
+            | Statement::Return { span, .. }
+            | Statement::Call { span, .. } => *span,
+        }
+    }
 }
 
 
+// A name introduced by `args` or `int`
 #[derive(Debug, Clone)]
-pub struct Program {
-    
-    pub arg_declarations: Vec<String>,
-    pub var_declarations: Vec<String>,
+pub struct Declaration {
+    pub name: String,
+    pub span: Span,
+    // `Some(n)` for an array declared `int name[n];`
+    pub length: Option<u64>,
+}
+
+
+// fn name(args) { int vars; statements }
+#[derive(Debug, Clone)]
+pub struct Function {
+    pub name: String,
+    pub name_span: Span,
+    pub arg_declarations: Vec<Declaration>,
+    pub var_declarations: Vec<Declaration>,
     pub statements: Vec<Statement>,
-    pub return_ident: String,
+    // where control arrives if a path does not return
+    pub end_span: Span,
+}
+
 
+// extern fn name(args);  -- defined elsewhere (in C), called with the
+// platform's C symbol name; arguments and result are 64-bit integers
+#[derive(Debug, Clone)]
+pub struct ExternFunction {
+    pub name: String,
+    pub name_span: Span,
+    pub arg_declarations: Vec<Declaration>,
+}
+
+
+// [export] global int name [= value];  -- one 64-bit variable shared by
+// every function; `export` makes it visible to C under the same name
+#[derive(Debug, Clone)]
+pub struct GlobalVariable {
+    pub name: String,
+    pub span: Span,
+    pub initial_value: u64,
+    pub exported: bool,
+}
+
+
+// Every function in a source file, in order. A file in the original
+// `args ...; int ...; statements` form holds one function named `foo`.
+#[derive(Debug, Clone)]
+pub struct Program {
+    pub globals: Vec<GlobalVariable>,
+    pub externs: Vec<ExternFunction>,
+    pub functions: Vec<Function>,
 }
 
 
 // ENDS *********: Control Flow Extension :********* ///
 
+#[allow(dead_code)] // debugging aid, not called by the compiler itself
 pub fn print_level_order(tree_root: &Rc<RefCell<ASTNode>>) {
     // A queue for BFS traversal
     use ASTNode::*;
@@ -72,24 +236,82 @@ pub fn print_level_order(tree_root: &Rc<RefCell<ASTNode>>) {
         for _ in 0..traversal_queue.len() {
             let current = traversal_queue.pop_front().unwrap();
             match &*current.borrow() {
-                Add(left, right) => {
+                Add(left, right, _) => {
                     print!("+ ");
                     next_level_nodes.push(left.clone());
                     next_level_nodes.push(right.clone());
                 }
-                Multiply(left, right) => {
+                Multiply(left, right, _) => {
                     print!("* ");
                     next_level_nodes.push(left.clone());
                     next_level_nodes.push(right.clone());
                 }
-                Sub(left, right) => {            
+                Sub(left, right, _) => {            
                     print!("- ");
                     next_level_nodes.push(left.clone());
                     next_level_nodes.push(right.clone());
                 }
+                Divide(left, right, _) => {
+                    print!("/ ");
+                    next_level_nodes.push(left.clone());
+                    next_level_nodes.push(right.clone());
+                }
+                Modulo(left, right, _) => {
+                    print!("% ");
+                    next_level_nodes.push(left.clone());
+                    next_level_nodes.push(right.clone());
+                }
 
-                Number(value) => print!("{} ", value),
-                Identifier(name) => print!("{} ", name),
+                Neg(operand, _) => {
+                    print!("neg ");
+                    next_level_nodes.push(operand.clone());
+                }
+                BitNot(operand, _) => {
+                    print!("~ ");
+                    next_level_nodes.push(operand.clone());
+                }
+                BitAnd(left, right, _) => {
+                    print!("& ");
+                    next_level_nodes.push(left.clone());
+                    next_level_nodes.push(right.clone());
+                }
+                BitOr(left, right, _) => {
+                    print!("| ");
+                    next_level_nodes.push(left.clone());
+                    next_level_nodes.push(right.clone());
+                }
+                BitXor(left, right, _) => {
+                    print!("^ ");
+                    next_level_nodes.push(left.clone());
+                    next_level_nodes.push(right.clone());
+                }
+                ShiftLeft(left, right, _) => {
+                    print!("<< ");
+                    next_level_nodes.push(left.clone());
+                    next_level_nodes.push(right.clone());
+                }
+                ShiftRight(left, right, _) => {
+                    print!(">> ");
+                    next_level_nodes.push(left.clone());
+                    next_level_nodes.push(right.clone());
+                }
+                ShiftRightLogical(left, right, _) => {
+                    print!(">>> ");
+                    next_level_nodes.push(left.clone());
+                    next_level_nodes.push(right.clone());
+                }
+
+                Condition(_, _) => print!("cond "),
+                Call(name, args, _) => {
+                    print!("{}() ", name);
+                    next_level_nodes.extend(args.iter().cloned());
+                }
+                Index(name, index, _) => {
+                    print!("{}[] ", name);
+                    next_level_nodes.push(index.clone());
+                }
+                Number(value, _) => print!("{} ", value),
+                Identifier(name, _) => print!("{} ", name),
             }
         }
 
@@ -1,12 +1,75 @@
-use crate::ast::{ASTNode, BooleanExpression, Program, Statement};
+use crate::ast::{ASTNode, BooleanExpression, Function, GlobalVariable, Program, Statement};
+use crate::diagnostics::{Diagnostic, E_UNKNOWN_VARIABLE};
+use crate::span::Span;
 use std::cell::RefCell;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::rc::Rc;
 
+/// What `/` and `%` do when the divisor is 0, selected with `--div-zero=`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum DivisionByZero {
+    // print where it happened to stderr and abort
+    #[default]
+    Trap,
+    // emit a bare `idivq`, like C (on x86-64 the CPU raises SIGFPE)
+    Undefined,
+}
+
+impl DivisionByZero {
+    pub fn from_flag(value: &str) -> Option<Self> {
+        match value {
+            "trap" => Some(DivisionByZero::Trap),
+            "undefined" => Some(DivisionByZero::Undefined),
+            _ => None,
+        }
+    }
+}
+
+/// Switches that change the emitted code, set from the command line.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CodegenOptions {
+    // `--zero-init-locals`: store 0 into every `int` slot in the prologue
+    pub zero_init_locals: bool,
+    pub div_zero: DivisionByZero,
+    // `--bounds-check`: trap on an array index outside `0..length`
+    pub bounds_check: bool,
+}
+
+/// C functions the runtime traps call by name (see `emit_traps`); a
+/// program may not define a function or global with one of these names.
+pub const RUNTIME_FUNCTIONS: [&str; 2] = ["write", "abort"];
+
+// Name of a C function as the platform's linker sees it
+fn c_symbol(name: &str) -> String {
+    if cfg!(target_os = "macos") {
+        format!("_{}", name)
+    } else {
+        name.to_string()
+    }
+}
+
 pub struct X86Writer {
     pub out: String,
     lbl: usize,
     offsets: HashMap<String, i64>,
+    // length of every array in the current function; its offset is element 0
+    lengths: HashMap<String, u64>,
+    diagnostics: Vec<Diagnostic>,
+    div_zero: DivisionByZero,
+    bounds_check: bool,
+    // (label, message) for every division or index that traps at run time
+    trap_sites: Vec<(String, String)>,
+    // (continue target, break target) of every enclosing loop, innermost last
+    loops: Vec<(String, String)>,
+    // the shared epilogue every `return` jumps to
+    return_lbl: String,
+    // functions declared `extern`, called by their C symbol name
+    externs: HashSet<String>,
+    // names declared `global`, addressed relative to %rip
+    globals: HashSet<String>,
+    // 8-byte values pushed below the frame right now; a call pads the
+    // stack when this is odd so that %rsp is 16-byte aligned at `call`
+    depth: usize,
 }
 
 impl X86Writer {
@@ -15,36 +78,123 @@ impl X86Writer {
             out: String::new(),
             lbl: 0,
             offsets: HashMap::new(),
+            lengths: HashMap::new(),
+            diagnostics: Vec::new(),
+            div_zero: DivisionByZero::default(),
+            bounds_check: false,
+            trap_sites: Vec::new(),
+            loops: Vec::new(),
+            return_lbl: String::new(),
+            externs: HashSet::new(),
+            globals: HashSet::new(),
+            depth: 0,
         }
     }
 
+    fn push(&mut self, reg: &str) {
+        self.out.push_str(&format!("pushq {}\n", reg));
+        self.depth += 1;
+    }
+
+    fn pop(&mut self, reg: &str) {
+        self.out.push_str(&format!("popq {}\n", reg));
+        self.depth -= 1;
+    }
+
     fn fresh_lbl(&mut self, base: &str) -> String {
         self.lbl += 1;
         format!("{}.{}", base, self.lbl)
     }
 
-    fn slot(&self, var: &str) -> i64 {
-        *self.offsets.get(var).expect("unknown variable in slot()")
+    // Memory operand for `var`: its stack slot, or its label if it is a
+    // global the function does not hide. An unknown name is recorded as a
+    // diagnostic and a dummy slot is returned so the rest of the program
+    // still gets checked.
+    fn location(&mut self, var: &str, span: Span) -> String {
+        if let Some(off) = self.offsets.get(var) {
+            return format!("{}(%rbp)", off);
+        }
+        if self.globals.contains(var) {
+            return format!("{}(%rip)", var);
+        }
+        self.diagnostics.push(
+            Diagnostic::error(E_UNKNOWN_VARIABLE, format!("unknown variable `{}`", var), span)
+                .with_label("not declared in `args`, `int` or `global`"),
+        );
+        "0(%rbp)".to_string()
+    }
+
+    // Offset of element 0 and length of the array `var`
+    fn array(&mut self, var: &str, span: Span) -> (i64, u64) {
+        if let (Some(off), Some(length)) = (self.offsets.get(var), self.lengths.get(var)) {
+            return (*off, *length);
+        }
+        self.diagnostics.push(
+            Diagnostic::error(E_UNKNOWN_VARIABLE, format!("unknown array `{}`", var), span)
+                .with_label("not declared as an array in `int`"),
+        );
+        (0, 1)
     }
 }
 
+// Registers that carry the first six arguments (System V)
+const ARG_REGS: [&str; 6] = ["%rdi", "%rsi", "%rdx", "%rcx", "%r8", "%r9"];
+
 // ===============================================================
 // Top-level: full program → x86-64
 // ===============================================================
-pub fn generate_program_x86(p: &Program) -> String {
+pub fn generate_program_x86(p: &Program, options: &CodegenOptions) -> Result<String, Vec<Diagnostic>> {
     let mut w = X86Writer::new();
+    w.div_zero = options.div_zero;
+    w.bounds_check = options.bounds_check;
+    w.externs = p.externs.iter().map(|e| e.name.clone()).collect();
+    w.globals = p.globals.iter().map(|g| g.name.clone()).collect();
+
+    w.out.push_str(".text\n");
+    for f in &p.functions {
+        emit_function(f, options, &mut w);
+    }
+
+    emit_traps(&mut w);
+    emit_globals(&p.globals, &mut w);
 
-    // 1) Assign stack slots: args + vars, 8 bytes each
+    if !w.diagnostics.is_empty() {
+        return Err(w.diagnostics);
+    }
+    Ok(w.out)
+}
+
+// ===============================================================
+// One function: prologue, body, shared epilogue
+// ===============================================================
+fn emit_function(f: &Function, options: &CodegenOptions, w: &mut X86Writer) {
+    w.offsets.clear();
+    w.lengths.clear();
+    w.return_lbl = w.fresh_lbl("return");
+
+    // 1) Assign stack slots: register args + vars, 8 bytes each.
+    //    Arguments 7+ already sit in the caller's frame, above the
+    //    return address and saved %rbp: 16(%rbp), 24(%rbp), ...
+    //    An array of n takes n contiguous slots with element 0 at the
+    //    lowest address, so a[i] is at offset + 8*i.
 
    
     let mut offset: i64 = -8;
 
-    for a in &p.arg_declarations {
-        w.offsets.insert(a.clone(), offset);
-        offset -= 8;
+    for (i, a) in f.arg_declarations.iter().enumerate() {
+        if i < ARG_REGS.len() {
+            w.offsets.insert(a.name.clone(), offset);
+            offset -= 8;
+        } else {
+            w.offsets.insert(a.name.clone(), 16 + 8 * (i - ARG_REGS.len()) as i64);
+        }
     }
-    for v in &p.var_declarations {
-        w.offsets.insert(v.clone(), offset);
+    for v in &f.var_declarations {
+        if let Some(length) = v.length {
+            offset -= 8 * (length as i64 - 1);
+            w.lengths.insert(v.name.clone(), length);
+        }
+        w.offsets.insert(v.name.clone(), offset);
         offset -= 8;
     }
 
@@ -58,7 +208,7 @@ pub fn generate_program_x86(p: &Program) -> String {
     }
 
     // 2) Prologue
-    w.out.push_str(".text\n.global foo\nfoo:\n");
+    w.out.push_str(&format!(".global {}\n{}:\n", f.name, f.name));
     w.out.push_str("pushq %rbp\n");
     w.out.push_str("movq %rsp, %rbp\n");
 
@@ -67,33 +217,42 @@ pub fn generate_program_x86(p: &Program) -> String {
             .push_str(&format!("subq ${}, %rsp\n", frame_size));
     }
 
-    // 3) Store incoming args into their stack slots
-    let arg_regs = ["%rdi", "%rsi", "%rdx", "%rcx", "%r8", "%r9"];
-    for (i, a) in p.arg_declarations.iter().enumerate() {
-        let off = w.slot(a);
-        w.out
-            .push_str(&format!("movq {}, {}(%rbp)\n", arg_regs[i], off));
+    // 3) Store incoming register args into their stack slots
+    for (a, reg) in f.arg_declarations.iter().zip(ARG_REGS) {
+        let slot = w.location(&a.name, a.span);
+        w.out.push_str(&format!("movq {}, {}\n", reg, slot));
     }
 
-    // 4) Emit statements
-    for s in &p.statements {
-        emit_stmt(s, &mut w);
+    if options.zero_init_locals {
+        for v in &f.var_declarations {
+            let slot = w.location(&v.name, v.span);
+            match v.length {
+                None => w.out.push_str(&format!("movq $0, {}\n", slot)),
+                // every argument is already stored, so %rdi and %rcx are free
+                Some(length) => {
+                    w.out.push_str(&format!("leaq {}, %rdi\n", slot));
+                    w.out.push_str(&format!("movq ${}, %rcx\n", length));
+                    w.out.push_str("xorl %eax, %eax\n");
+                    w.out.push_str("rep stosq\n");
+                }
+            }
+        }
     }
 
-    // 5) Load return value into %rax
-    let roff = w.slot(&p.return_ident);
-    w.out
-        .push_str(&format!("movq {}(%rbp), %rax\n", roff));
+    // 4) Emit statements; every path ends in a `return` that leaves
+    //    its value in %rax and jumps to the epilogue
+    for s in &f.statements {
+        emit_stmt(s, w);
+    }
 
-    // 6) Epilogue
+    // 5) Epilogue
+    w.out.push_str(&format!("{}:\n", w.return_lbl));
     if frame_size > 0 {
         w.out
             .push_str(&format!("addq ${}, %rsp\n", frame_size));
     }
     w.out.push_str("popq %rbp\n");
     w.out.push_str("ret\n");
-
-    w.out
 }
 
 // ===============================================================
@@ -101,22 +260,50 @@ pub fn generate_program_x86(p: &Program) -> String {
 // ===============================================================
 fn emit_stmt(s: &Statement, w: &mut X86Writer) {
     match s {
-        Statement::Assign { name, expression } => {
+        Statement::Assign { name, name_span, expression, .. } => {
             emit_expr(expression, w); 
-            let off = w.slot(name);
+            let target = w.location(name, *name_span);
             w.out
-                .push_str(&format!("movq %rax, {}(%rbp)\n", off));
+                .push_str(&format!("movq %rax, {}\n", target));
+        }
+
+        Statement::AssignElement {
+            name,
+            name_span,
+            index,
+            expression,
+            ..
+        } => {
+            let base = emit_index(name, index, *name_span, w);
+            w.push("%rax");
+            emit_expr(expression, w);
+            w.pop("%rcx");
+            w.out.push_str(&format!("movq %rax, {}(%rbp,%rcx,8)\n", base));
         }
 
         Statement::If {
             condition,
             then_statements,
             else_statements,
+            ..
         } => {
             let l_then = w.fresh_lbl("if.then");
-            let l_else = w.fresh_lbl("if.else");
             let l_end = w.fresh_lbl("if.end");
 
+            // Without an else branch a false condition goes straight to the end
+            if else_statements.is_empty() {
+                emit_bool(condition, &l_then, &l_end, w);
+
+                w.out.push_str(&format!("{}:\n", l_then));
+                for s in then_statements {
+                    emit_stmt(s, w);
+                }
+
+                w.out.push_str(&format!("{}:\n", l_end));
+                return;
+            }
+
+            let l_else = w.fresh_lbl("if.else");
             emit_bool(condition, &l_then, &l_else, w);
 
             w.out.push_str(&format!("{}:\n", l_then));
@@ -125,16 +312,16 @@ fn emit_stmt(s: &Statement, w: &mut X86Writer) {
             }
             w.out.push_str(&format!("jmp {}\n", l_end));
 
+            // the else branch falls through to the end
             w.out.push_str(&format!("{}:\n", l_else));
             for s in else_statements {
                 emit_stmt(s, w);
             }
-            w.out.push_str(&format!("jmp {}\n", l_end));
 
             w.out.push_str(&format!("{}:\n", l_end));
         }
 
-        Statement::While { condition, body } => {
+        Statement::While { condition, body, .. } => {
             let l_cond = w.fresh_lbl("while.cond");
             let l_body = w.fresh_lbl("while.body");
             let l_end = w.fresh_lbl("while.end");
@@ -143,12 +330,83 @@ fn emit_stmt(s: &Statement, w: &mut X86Writer) {
             emit_bool(condition, &l_body, &l_end, w);
 
             w.out.push_str(&format!("{}:\n", l_body));
+            w.loops.push((l_cond.clone(), l_end.clone()));
             for s in body {
                 emit_stmt(s, w);
             }
+            w.loops.pop();
             w.out.push_str(&format!("jmp {}\n", l_cond));
             w.out.push_str(&format!("{}:\n", l_end));
         }
+
+        Statement::For {
+            init,
+            condition,
+            step,
+            body,
+            ..
+        } => {
+            let l_cond = w.fresh_lbl("for.cond");
+            let l_body = w.fresh_lbl("for.body");
+            let l_step = w.fresh_lbl("for.step");
+            let l_end = w.fresh_lbl("for.end");
+
+            if let Some(init) = init {
+                emit_stmt(init, w);
+            }
+
+            w.out.push_str(&format!("{}:\n", l_cond));
+            emit_bool(condition, &l_body, &l_end, w);
+
+            w.out.push_str(&format!("{}:\n", l_body));
+            w.loops.push((l_step.clone(), l_end.clone()));
+            for s in body {
+                emit_stmt(s, w);
+            }
+            w.loops.pop();
+
+            w.out.push_str(&format!("{}:\n", l_step));
+            if let Some(step) = step {
+                emit_stmt(step, w);
+            }
+            w.out.push_str(&format!("jmp {}\n", l_cond));
+            w.out.push_str(&format!("{}:\n", l_end));
+        }
+
+        Statement::DoWhile { body, condition, .. } => {
+            let l_body = w.fresh_lbl("do.body");
+            let l_cond = w.fresh_lbl("do.cond");
+            let l_end = w.fresh_lbl("do.end");
+
+            w.out.push_str(&format!("{}:\n", l_body));
+            w.loops.push((l_cond.clone(), l_end.clone()));
+            for s in body {
+                emit_stmt(s, w);
+            }
+            w.loops.pop();
+
+            w.out.push_str(&format!("{}:\n", l_cond));
+            emit_bool(condition, &l_body, &l_end, w);
+            w.out.push_str(&format!("{}:\n", l_end));
+        }
+
+        Statement::Return { expression, .. } => {
+            emit_expr(expression, w);
+            w.out.push_str(&format!("jmp {}\n", w.return_lbl));
+        }
+
+        // the result left in %rax is simply not used
+        Statement::Call { expression, .. } => emit_expr(expression, w),
+
+        // the semantic pass guarantees an enclosing loop
+        Statement::Break { .. } => {
+            let (_, l_end) = w.loops.last().expect("`break` outside a loop");
+            w.out.push_str(&format!("jmp {}\n", l_end));
+        }
+        Statement::Continue { .. } => {
+            let (l_cond, _) = w.loops.last().expect("`continue` outside a loop");
+            w.out.push_str(&format!("jmp {}\n", l_cond));
+        }
     }
 }
 
@@ -159,41 +417,249 @@ fn emit_expr(node: &Rc<RefCell<ASTNode>>, w: &mut X86Writer) {
     use ASTNode::*;
 
     match &*node.borrow() {
-        Number(n) => {
-            w.out.push_str(&format!("movq ${}, %rax\n", n));
+        Number(n, _) => {
+            w.out.push_str(&load_immediate(*n, "%rax"));
+        }
+
+        Identifier(name, span) => {
+            let source = w.location(name, *span);
+            w.out.push_str(&format!("movq {}, %rax\n", source));
         }
 
-        Identifier(name) => {
-            let off = w.slot(name);
-            w.out.push_str(&format!("movq {}(%rbp), %rax\n", off));
+        Index(name, index, span) => {
+            let base = emit_index(name, index, *span, w);
+            w.out.push_str(&format!("movq {}(%rbp,%rax,8), %rax\n", base));
         }
 
-        Add(l, r) => {
+        Add(l, r, _) => {
             emit_expr(l, w);        
-            w.out.push_str("pushq %rax\n");
+            w.push("%rax");
             emit_expr(r, w);   
-            w.out.push_str("popq %rcx\n");
+            w.pop("%rcx");
             w.out.push_str("addq %rcx, %rax\n");
         }
 
-        Multiply(l, r) => {
+        Multiply(l, r, _) => {
             emit_expr(l, w);     
-            w.out.push_str("pushq %rax\n");
+            w.push("%rax");
             emit_expr(r, w);      
-            w.out.push_str("popq %rcx\n"); 
+            w.pop("%rcx"); 
             w.out.push_str("imulq %rcx, %rax\n");
         }
 
-        Sub(l, r) => {
+        Sub(l, r, _) => {
             // result = left - right
             emit_expr(l, w);     
-            w.out.push_str("pushq %rax\n");
+            w.push("%rax");
             emit_expr(r, w);         
-            w.out.push_str("popq %rcx\n"); // 
+            w.pop("%rcx");
            
             w.out.push_str("subq %rax, %rcx\n");
             w.out.push_str("movq %rcx, %rax\n");
         }
+
+        Neg(operand, _) => {
+            emit_expr(operand, w);
+            w.out.push_str("negq %rax\n");
+        }
+
+        Condition(b, _) => emit_bool_value(b, w),
+
+        Call(name, args, _) => emit_call(name, args, w),
+
+        BitNot(operand, _) => {
+            emit_expr(operand, w);
+            w.out.push_str("notq %rax\n");
+        }
+
+        BitAnd(l, r, _) => emit_bitwise(l, r, "andq", w),
+        BitOr(l, r, _) => emit_bitwise(l, r, "orq", w),
+        BitXor(l, r, _) => emit_bitwise(l, r, "xorq", w),
+
+        ShiftLeft(l, r, _) => emit_shift(l, r, "salq", w),
+        ShiftRight(l, r, _) => emit_shift(l, r, "sarq", w),
+        ShiftRightLogical(l, r, _) => emit_shift(l, r, "shrq", w),
+
+        Divide(l, r, span) => {
+            emit_division(l, r, *span, w);
+            // quotient is already in %rax
+        }
+
+        Modulo(l, r, span) => {
+            emit_division(l, r, *span, w);
+            w.out.push_str("movq %rdx, %rax\n"); // remainder
+        }
+    }
+}
+
+// %rax = name(args...), System V style: the first six arguments in
+// %rdi, %rsi, ..., the rest on the stack with the seventh at 0(%rsp),
+// result in %rax. Values pending in the enclosing expression are all on
+// the stack, so nothing caller-saved needs preserving around the call.
+fn emit_call(name: &str, args: &[Rc<RefCell<ASTNode>>], w: &mut X86Writer) {
+    // Reserve the stack arguments' slots first, plus 8 bytes of padding
+    // if %rsp would otherwise be misaligned at the `call`
+    let on_stack = args.len().saturating_sub(ARG_REGS.len());
+    let reserved = on_stack + (w.depth + on_stack) % 2;
+    if reserved > 0 {
+        w.out.push_str(&format!("subq ${}, %rsp\n", 8 * reserved));
+        w.depth += reserved;
+    }
+
+    // Evaluate left to right: register arguments are pushed, stack
+    // arguments stored into their slot below the six pushed values
+    for (i, arg) in args.iter().enumerate() {
+        emit_expr(arg, w);
+        if i < ARG_REGS.len() {
+            w.push("%rax");
+        } else {
+            w.out.push_str(&format!("movq %rax, {}(%rsp)\n", 8 * i));
+        }
+    }
+    for reg in ARG_REGS.iter().take(args.len()).rev() {
+        w.pop(reg);
+    }
+
+    if w.externs.contains(name) {
+        // %al = number of vector registers used, in case the C function is variadic
+        w.out.push_str("xorl %eax, %eax\n");
+        w.out.push_str(&format!("call {}\n", c_symbol(name)));
+    } else {
+        w.out.push_str(&format!("call {}\n", name));
+    }
+    if reserved > 0 {
+        w.out.push_str(&format!("addq ${}, %rsp\n", 8 * reserved));
+        w.depth -= reserved;
+    }
+}
+
+// %rax = left OP right for a commutative bitwise OP
+fn emit_bitwise(l: &Rc<RefCell<ASTNode>>, r: &Rc<RefCell<ASTNode>>, op: &str, w: &mut X86Writer) {
+    emit_expr(l, w);
+    w.push("%rax");
+    emit_expr(r, w);
+    w.pop("%rcx");
+    w.out.push_str(&format!("{} %rcx, %rax\n", op));
+}
+
+// %rax = left shifted by right; a variable count has to be in %cl
+fn emit_shift(l: &Rc<RefCell<ASTNode>>, r: &Rc<RefCell<ASTNode>>, op: &str, w: &mut X86Writer) {
+    if let ASTNode::Number(count, _) = &*r.borrow() {
+        emit_expr(l, w);
+        w.out.push_str(&format!("{} ${}, %rax\n", op, count % 64));
+        return;
+    }
+
+    emit_expr(l, w);
+    w.push("%rax");
+    emit_expr(r, w);
+    w.out.push_str("movq %rax, %rcx\n"); // count
+    w.pop("%rax"); // value
+    w.out.push_str(&format!("{} %cl, %rax\n", op));
+}
+
+// %rax = left / right, %rdx = left % right (signed, truncating)
+fn emit_division(l: &Rc<RefCell<ASTNode>>, r: &Rc<RefCell<ASTNode>>, span: Span, w: &mut X86Writer) {
+    emit_expr(l, w);
+    w.push("%rax");
+    emit_expr(r, w);
+    w.out.push_str("movq %rax, %rcx\n"); // divisor
+    w.pop("%rax"); // dividend
+
+    if w.div_zero == DivisionByZero::Trap {
+        let site = w.fresh_lbl("div.zero");
+        w.out.push_str("testq %rcx, %rcx\n");
+        w.out.push_str(&format!("je {}\n", site));
+        let message = format!("error: division by zero at line {}, column {}\n", span.line, span.column);
+        w.trap_sites.push((site, message));
+    }
+
+    w.out.push_str("cqto\n"); // sign-extend %rax into %rdx:%rax
+    w.out.push_str("idivq %rcx\n");
+}
+
+// Leaves the index of `name[index]` in %rax, checked against the
+// array's length under `--bounds-check`; returns the offset of element 0.
+fn emit_index(name: &str, index: &Rc<RefCell<ASTNode>>, span: Span, w: &mut X86Writer) -> i64 {
+    let (base, length) = w.array(name, span);
+    emit_expr(index, w);
+
+    if w.bounds_check {
+        // one unsigned compare also catches negative indices
+        let site = w.fresh_lbl("bounds");
+        w.out.push_str(&format!("cmpq ${}, %rax\n", length));
+        w.out.push_str(&format!("jae {}\n", site));
+        let message = format!(
+            "error: index out of bounds for `{}` (length {}) at line {}, column {}\n",
+            name, length, span.line, span.column
+        );
+        w.trap_sites.push((site, message));
+    }
+    base
+}
+
+// Out-of-line code for `--div-zero=trap` and `--bounds-check`: each site
+// loads its own message and shares one stub that writes it to stderr and aborts.
+fn emit_traps(w: &mut X86Writer) {
+    if w.trap_sites.is_empty() {
+        return;
+    }
+
+    let sites = std::mem::take(&mut w.trap_sites);
+    for (site, message) in &sites {
+        w.out.push_str(&format!("{}:\n", site));
+        w.out.push_str(&format!("leaq {}.msg(%rip), %rsi\n", site));
+        w.out.push_str(&format!("movq ${}, %rdx\n", message.len()));
+        w.out.push_str("jmp runtime.trap\n");
+    }
+
+    w.out.push_str("runtime.trap:\n");
+    w.out.push_str("andq $-16, %rsp\n"); // never returns, so just realign for the calls
+    w.out.push_str("movq $2, %rdi\n"); // stderr
+    w.out.push_str(&format!("call {}\n", c_symbol("write")));
+    w.out.push_str(&format!("call {}\n", c_symbol("abort")));
+
+    w.out.push_str(".data\n");
+    for (site, message) in &sites {
+        w.out.push_str(&format!("{}.msg:\n", site));
+        w.out.push_str(&format!(".ascii \"{}\"\n", message.escape_default()));
+    }
+}
+
+// Every `global`: initialised ones in `.data`, the rest in `.bss`
+// (zero-filled at load time). `export` makes the label visible to C.
+fn emit_globals(globals: &[GlobalVariable], w: &mut X86Writer) {
+    let (data, bss): (Vec<&GlobalVariable>, Vec<&GlobalVariable>) =
+        globals.iter().partition(|g| g.initial_value != 0);
+
+    for (section, vars) in [(".data", data), (".bss", bss)] {
+        if vars.is_empty() {
+            continue;
+        }
+        w.out.push_str(&format!("{}\n", section));
+        w.out.push_str(".p2align 3\n");
+        for g in vars {
+            if g.exported {
+                w.out.push_str(&format!(".global {}\n", g.name));
+            }
+            w.out.push_str(&format!("{}:\n", g.name));
+            if g.initial_value != 0 {
+                w.out.push_str(&format!(".quad {}\n", g.initial_value as i64));
+            } else {
+                w.out.push_str(".zero 8\n");
+            }
+        }
+    }
+}
+
+// `movq $imm` only takes a sign-extended 32-bit immediate;
+// anything wider needs `movabsq`.
+fn load_immediate(value: u64, reg: &str) -> String {
+    let signed = value as i64;
+    if i32::try_from(signed).is_ok() {
+        format!("movq ${}, {}\n", signed, reg)
+    } else {
+        format!("movabsq ${}, {}\n", signed, reg)
     }
 }
 
@@ -202,28 +668,49 @@ fn emit_expr(node: &Rc<RefCell<ASTNode>>, w: &mut X86Writer) {
 // ===============================================================
 fn emit_bool(b: &BooleanExpression, l_true: &str, l_false: &str, w: &mut X86Writer) {
     match b {
-        BooleanExpression::True => {
+        BooleanExpression::True(_) => {
             w.out.push_str(&format!("jmp {}\n", l_true));
         }
-        BooleanExpression::False => {
+        BooleanExpression::False(_) => {
             w.out.push_str(&format!("jmp {}\n", l_false));
         }
 
-        BooleanExpression::CompareLessThan(l, r) => {
+        BooleanExpression::CompareLessThan(l, r, _) => {
             emit_cmp(l, r, "jl", l_true, l_false, w);
         }
-        BooleanExpression::CompareLessThanEqualTo(l, r) => {
+        BooleanExpression::CompareLessThanEqualTo(l, r, _) => {
             emit_cmp(l, r, "jle", l_true, l_false, w);
         }
-        BooleanExpression::CompareGreaterThan(l, r) => {
+        BooleanExpression::CompareGreaterThan(l, r, _) => {
             emit_cmp(l, r, "jg", l_true, l_false, w);
         }
-        BooleanExpression::CompareGreaterThanEqualTo(l, r) => {
+        BooleanExpression::CompareGreaterThanEqualTo(l, r, _) => {
             emit_cmp(l, r, "jge", l_true, l_false, w);
         }
-        BooleanExpression::CompareEqualTo(l, r) => {
+        BooleanExpression::CompareEqualTo(l, r, _) => {
             emit_cmp(l, r, "je", l_true, l_false, w);
         }
+        BooleanExpression::CompareNotEqualTo(l, r, _) => {
+            emit_cmp(l, r, "jne", l_true, l_false, w);
+        }
+
+        // Short-circuit: the left side jumps straight to the outcome
+        // when it alone decides it, otherwise falls into the right side
+        BooleanExpression::And(l, r, _) => {
+            let l_rhs = w.fresh_lbl("and.rhs");
+            emit_bool(l, &l_rhs, l_false, w);
+            w.out.push_str(&format!("{}:\n", l_rhs));
+            emit_bool(r, l_true, l_false, w);
+        }
+        BooleanExpression::Or(l, r, _) => {
+            let l_rhs = w.fresh_lbl("or.rhs");
+            emit_bool(l, l_true, &l_rhs, w);
+            w.out.push_str(&format!("{}:\n", l_rhs));
+            emit_bool(r, l_true, l_false, w);
+        }
+        BooleanExpression::Not(inner, _) => {
+            emit_bool(inner, l_false, l_true, w);
+        }
     }
 }
 
@@ -235,11 +722,59 @@ fn emit_cmp(
     l_false: &str,
     w: &mut X86Writer,
 ) {
+    emit_compare(l, r, w);
+    w.out.push_str(&format!("{} {}\n", jmp, l_true));
+    w.out.push_str(&format!("jmp {}\n", l_false));
+}
 
+// Sets the flags for `left cmp right`
+fn emit_compare(l: &Rc<RefCell<ASTNode>>, r: &Rc<RefCell<ASTNode>>, w: &mut X86Writer) {
     emit_expr(l, w);                 // %rax = left
-    w.out.push_str("movq %rax, %rcx\n");
+    w.push("%rax"); // the right side may use %rcx itself
     emit_expr(r, w);                 // %rax = right
+    w.pop("%rcx");
     w.out.push_str("cmpq %rax, %rcx\n");
-    w.out.push_str(&format!("{} {}\n", jmp, l_true));
-    w.out.push_str(&format!("jmp {}\n", l_false));
+}
+
+// ===============================================================
+// Boolean expressions as values  (%rax = 1 or 0)
+// ===============================================================
+fn emit_bool_value(b: &BooleanExpression, w: &mut X86Writer) {
+    match b {
+        BooleanExpression::True(_) => w.out.push_str("movq $1, %rax\n"),
+        BooleanExpression::False(_) => w.out.push_str("movq $0, %rax\n"),
+
+        BooleanExpression::CompareLessThan(l, r, _) => emit_set(l, r, "setl", w),
+        BooleanExpression::CompareLessThanEqualTo(l, r, _) => emit_set(l, r, "setle", w),
+        BooleanExpression::CompareGreaterThan(l, r, _) => emit_set(l, r, "setg", w),
+        BooleanExpression::CompareGreaterThanEqualTo(l, r, _) => emit_set(l, r, "setge", w),
+        BooleanExpression::CompareEqualTo(l, r, _) => emit_set(l, r, "sete", w),
+        BooleanExpression::CompareNotEqualTo(l, r, _) => emit_set(l, r, "setne", w),
+
+        BooleanExpression::Not(inner, _) => {
+            emit_bool_value(inner, w);
+            w.out.push_str("xorq $1, %rax\n");
+        }
+
+        // `&&` / `||` still short-circuit, so branch and load the result
+        BooleanExpression::And(_, _, _) | BooleanExpression::Or(_, _, _) => {
+            let l_true = w.fresh_lbl("cond.true");
+            let l_false = w.fresh_lbl("cond.false");
+            let l_end = w.fresh_lbl("cond.end");
+
+            emit_bool(b, &l_true, &l_false, w);
+            w.out.push_str(&format!("{}:\n", l_true));
+            w.out.push_str("movq $1, %rax\n");
+            w.out.push_str(&format!("jmp {}\n", l_end));
+            w.out.push_str(&format!("{}:\n", l_false));
+            w.out.push_str("movq $0, %rax\n");
+            w.out.push_str(&format!("{}:\n", l_end));
+        }
+    }
+}
+
+fn emit_set(l: &Rc<RefCell<ASTNode>>, r: &Rc<RefCell<ASTNode>>, set: &str, w: &mut X86Writer) {
+    emit_compare(l, r, w);
+    w.out.push_str(&format!("{} %al\n", set));
+    w.out.push_str("movzbq %al, %rax\n");
 }
@@ -0,0 +1,319 @@
+use crate::span::Span;
+
+// ===============================================================
+// Error codes
+// One stable code per kind of problem, grouped by compiler phase:
+//   E00xx scanner, E01xx parser, E02xx name resolution, E03xx codegen
+// Lints (see lints.rs) use W-codes; they are warnings unless promoted.
+// ===============================================================
+pub const E_INVALID_CHARACTER: &str = "E0001";
+pub const E_UNTERMINATED_COMMENT: &str = "E0002";
+pub const E_LITERAL_TOO_LARGE: &str = "E0003";
+pub const E_MALFORMED_LITERAL: &str = "E0004";
+
+pub const E_UNEXPECTED_TOKEN: &str = "E0100";
+pub const E_UNMATCHED_BRACE: &str = "E0101";
+
+pub const E_UNDECLARED_VARIABLE: &str = "E0200";
+pub const E_DUPLICATE_DECLARATION: &str = "E0201";
+pub const E_SHADOWED_ARGUMENT: &str = "E0202";
+pub const E_USE_BEFORE_ASSIGNMENT: &str = "E0203";
+pub const E_JUMP_OUTSIDE_LOOP: &str = "E0204";
+pub const E_MISSING_RETURN: &str = "E0205";
+pub const E_DUPLICATE_FUNCTION: &str = "E0206";
+pub const E_UNDEFINED_FUNCTION: &str = "E0207";
+pub const E_ARITY_MISMATCH: &str = "E0208";
+pub const E_NOT_AN_ARRAY: &str = "E0209";
+pub const E_ARRAY_WITHOUT_INDEX: &str = "E0210";
+pub const E_ARRAY_LENGTH: &str = "E0211";
+pub const E_RESERVED_NAME: &str = "E0212";
+
+pub const W_UNUSED_VARIABLE: &str = "W0001";
+pub const W_UNUSED_ARGUMENT: &str = "W0002";
+pub const W_DEAD_STORE: &str = "W0003";
+
+pub const E_UNKNOWN_VARIABLE: &str = "E0300";
+
+/// Whether a diagnostic stops compilation.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+impl Severity {
+    pub fn as_str(self) -> &'static str {
+        match self {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        }
+    }
+}
+
+/// Extra source location shown alongside the main one,
+/// e.g. "variable declared here".
+#[derive(Debug, Clone)]
+pub struct Label {
+    pub span: Span,
+    pub message: String,
+}
+
+/// A machine-applicable fix: replace the text at `span` with `replacement`
+/// (an empty span means "insert here").
+#[derive(Debug, Clone)]
+pub struct Suggestion {
+    pub message: String,
+    pub span: Span,
+    pub replacement: String,
+}
+
+/// A problem found in the source program, anchored at `span`.
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub code: &'static str,
+    pub message: String,
+    pub span: Span,
+    // text printed under the primary caret
+    pub label: Option<String>,
+    pub secondary: Vec<Label>,
+    pub notes: Vec<String>,
+    pub suggestions: Vec<Suggestion>,
+}
+
+impl Diagnostic {
+    pub fn error(code: &'static str, message: impl Into<String>, span: Span) -> Self {
+        Diagnostic {
+            severity: Severity::Error,
+            code,
+            message: message.into(),
+            span,
+            label: None,
+            secondary: Vec::new(),
+            notes: Vec::new(),
+            suggestions: Vec::new(),
+        }
+    }
+
+    pub fn warning(code: &'static str, message: impl Into<String>, span: Span) -> Self {
+        Diagnostic {
+            severity: Severity::Warning,
+            ..Diagnostic::error(code, message, span)
+        }
+    }
+
+    pub fn with_label(mut self, label: impl Into<String>) -> Self {
+        self.label = Some(label.into());
+        self
+    }
+
+    pub fn with_secondary(mut self, span: Span, message: impl Into<String>) -> Self {
+        self.secondary.push(Label { span, message: message.into() });
+        self
+    }
+
+    pub fn with_note(mut self, note: impl Into<String>) -> Self {
+        self.notes.push(note.into());
+        self
+    }
+
+    pub fn with_suggestion(mut self, message: impl Into<String>, span: Span, replacement: impl Into<String>) -> Self {
+        self.suggestions.push(Suggestion {
+            message: message.into(),
+            span,
+            replacement: replacement.into(),
+        });
+        self
+    }
+}
+
+/// How diagnostics are printed, selected with `--error-format=`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorFormat {
+    Human,
+    Json,
+}
+
+impl ErrorFormat {
+    pub fn from_flag(value: &str) -> Option<Self> {
+        match value {
+            "human" => Some(ErrorFormat::Human),
+            "json" => Some(ErrorFormat::Json),
+            _ => None,
+        }
+    }
+}
+
+// ===============================================================
+// Human-readable rendering
+//
+// error[E0300]: unknown variable `q`
+//  --> prog.rucomp:3:5
+//   |
+// 3 | x = q;
+//   |     ^ not declared in `args` or `int`
+//   |
+//   = note: ...
+// ===============================================================
+const TAB_WIDTH: usize = 4;
+
+pub fn render(d: &Diagnostic, file: &str, source: &str) -> String {
+    let mut out = format!("{}[{}]: {}\n", d.severity.as_str(), d.code, d.message);
+
+    // (span, marker, message) for every label, primary first
+    let mut labels: Vec<(Span, char, &str)> = vec![(d.span, '^', d.label.as_deref().unwrap_or(""))];
+    for l in &d.secondary {
+        labels.push((l.span, '-', l.message.as_str()));
+    }
+
+    let lines: Vec<&str> = source.split('\n').collect();
+    let mut shown: Vec<usize> = labels.iter().map(|(s, _, _)| s.line).collect();
+    shown.sort_unstable();
+    shown.dedup();
+
+    let gutter = shown.last().copied().unwrap_or(1).to_string().len();
+    let pad = " ".repeat(gutter);
+
+    out.push_str(&format!("{}--> {}:{}:{}\n", pad, file, d.span.line, d.span.column));
+    out.push_str(&format!("{} |\n", pad));
+
+    for (i, line_no) in shown.iter().enumerate() {
+        if i > 0 && *line_no > shown[i - 1] + 1 {
+            out.push_str(&format!("{}...\n", pad));
+        }
+
+        let text = lines.get(line_no - 1).copied().unwrap_or("").trim_end_matches('\r');
+        out.push_str(&format!("{:>width$} | {}\n", line_no, expand_tabs(text), width = gutter));
+
+        let mut on_line: Vec<_> = labels.iter().filter(|(s, _, _)| s.line == *line_no).collect();
+        on_line.sort_by_key(|(s, _, _)| s.column);
+
+        for (span, marker, message) in on_line {
+            let (indent, width) = underline_extent(text, *span, source);
+            let mut row = format!("{} | {}{}", pad, " ".repeat(indent), marker.to_string().repeat(width));
+            if !message.is_empty() {
+                row.push(' ');
+                row.push_str(message);
+            }
+            out.push_str(row.trim_end());
+            out.push('\n');
+        }
+    }
+
+    if !d.notes.is_empty() || !d.suggestions.is_empty() {
+        out.push_str(&format!("{} |\n", pad));
+        for note in &d.notes {
+            out.push_str(&format!("{} = note: {}\n", pad, note));
+        }
+        for fix in &d.suggestions {
+            out.push_str(&format!("{} = help: {}\n", pad, fix.message));
+        }
+    }
+
+    out
+}
+
+fn expand_tabs(text: &str) -> String {
+    text.replace('\t', &" ".repeat(TAB_WIDTH))
+}
+
+fn display_width(text: &str) -> usize {
+    text.chars().map(|c| if c == '\t' { TAB_WIDTH } else { 1 }).sum()
+}
+
+// Display column and width of the underline for `span` on its first line
+fn underline_extent(line_text: &str, span: Span, source: &str) -> (usize, usize) {
+    let before: String = line_text.chars().take(span.column.saturating_sub(1)).collect();
+    let covered = source.get(span.start..span.end).unwrap_or("");
+    let covered = covered.split('\n').next().unwrap_or("");
+    (display_width(&before), display_width(covered).max(1))
+}
+
+// ===============================================================
+// JSON rendering: one object per line, e.g.
+//
+// {"severity":"error","code":"E0100","message":"expected `;`, found `}`",
+//  "file":"prog.rucomp","span":{"start":20,"end":20,"line":3,"column":9,
+//  "end_line":3,"end_column":9},"label":"expected `;`","labels":[],
+//  "notes":[],"suggestions":[{"message":"insert `;`","span":{...},"replacement":";"}]}
+// ===============================================================
+pub fn render_json(d: &Diagnostic, file: &str, source: &str) -> String {
+    let labels: Vec<String> = d
+        .secondary
+        .iter()
+        .map(|l| format!("{{\"message\":{},\"span\":{}}}", json_string(&l.message), json_span(l.span, source)))
+        .collect();
+    let notes: Vec<String> = d.notes.iter().map(|n| json_string(n)).collect();
+    let suggestions: Vec<String> = d
+        .suggestions
+        .iter()
+        .map(|f| {
+            format!(
+                "{{\"message\":{},\"span\":{},\"replacement\":{}}}",
+                json_string(&f.message),
+                json_span(f.span, source),
+                json_string(&f.replacement)
+            )
+        })
+        .collect();
+
+    format!(
+        "{{\"severity\":\"{}\",\"code\":{},\"message\":{},\"file\":{},\"span\":{},\"label\":{},\"labels\":[{}],\"notes\":[{}],\"suggestions\":[{}]}}",
+        d.severity.as_str(),
+        json_string(d.code),
+        json_string(&d.message),
+        json_string(file),
+        json_span(d.span, source),
+        d.label.as_deref().map(json_string).unwrap_or_else(|| "null".to_string()),
+        labels.join(","),
+        notes.join(","),
+        suggestions.join(",")
+    )
+}
+
+fn json_span(span: Span, source: &str) -> String {
+    let (end_line, end_column) = line_column_at(source, span.end);
+    format!(
+        "{{\"start\":{},\"end\":{},\"line\":{},\"column\":{},\"end_line\":{},\"end_column\":{}}}",
+        span.start, span.end, span.line, span.column, end_line, end_column
+    )
+}
+
+// 1-based line and column of a byte offset
+fn line_column_at(source: &str, offset: usize) -> (usize, usize) {
+    let before = source.get(..offset).unwrap_or(source);
+    let line = before.matches('\n').count() + 1;
+    let line_start = before.rfind('\n').map(|i| i + 1).unwrap_or(0);
+    (line, before[line_start..].chars().count() + 1)
+}
+
+fn json_string(text: &str) -> String {
+    let mut out = String::from("\"");
+    for c in text.chars() {
+        match c {
+            '"' => out.push_str("\\\""),
+            '\\' => out.push_str("\\\\"),
+            '\n' => out.push_str("\\n"),
+            '\r' => out.push_str("\\r"),
+            '\t' => out.push_str("\\t"),
+            c if (c as u32) < 0x20 => out.push_str(&format!("\\u{:04x}", c as u32)),
+            c => out.push(c),
+        }
+    }
+    out.push('"');
+    out
+}
+
+pub fn has_errors(diagnostics: &[Diagnostic]) -> bool {
+    diagnostics.iter().any(|d| d.severity == Severity::Error)
+}
+
+/// Print every diagnostic to stderr, rendered against `source`.
+pub fn report(diagnostics: &[Diagnostic], file: &str, source: &str, format: ErrorFormat) {
+    for d in diagnostics {
+        match format {
+            ErrorFormat::Human => eprintln!("{}", render(d, file, source)),
+            ErrorFormat::Json => eprintln!("{}", render_json(d, file, source)),
+        }
+    }
+}
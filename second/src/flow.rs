@@ -0,0 +1,361 @@
+use crate::ast::{ASTNode, BooleanExpression, Function, Program, Statement};
+use crate::diagnostics::{Diagnostic, E_MISSING_RETURN, E_USE_BEFORE_ASSIGNMENT};
+use crate::span::Span;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+// ===============================================================
+// Definite assignment
+// Arguments arrive with a value; `int` locals do not. Walks the
+// statements in execution order tracking which locals are assigned
+// on every path (`definitely`) and on at least one path (`maybe`):
+//   - `if` keeps what both branches assign
+//   - `while` and `for` may run zero times, so their bodies add nothing
+//     definite, except through a `break` out of a `while true` loop
+//   - a `do` body always runs once, so what it assigns is definite
+//   - after `break` / `continue` nothing runs until control joins again,
+//     and after `return` nothing runs at all
+// A read of a local outside `definitely` is reported once per local.
+// Arrays are not tracked: their elements are usually filled by loops
+// this walk cannot count.
+// The same walk checks that the end of each function is unreachable,
+// i.e. that every path ends in `return`.
+// ===============================================================
+
+#[derive(Clone, Default)]
+struct Assigned {
+    definitely: HashSet<String>,
+    maybe: HashSet<String>,
+    // no path reaches this point (right after `break`, `continue` or `return`)
+    unreachable: bool,
+}
+
+impl Assigned {
+    fn insert(&mut self, name: &str) {
+        self.definitely.insert(name.to_string());
+        self.maybe.insert(name.to_string());
+    }
+
+    // State after control flow from `self` and `other` joins again
+    fn join(&self, other: &Assigned) -> Assigned {
+        if self.unreachable {
+            return other.clone();
+        }
+        if other.unreachable {
+            return self.clone();
+        }
+        Assigned {
+            definitely: self.definitely.intersection(&other.definitely).cloned().collect(),
+            maybe: self.maybe.union(&other.maybe).cloned().collect(),
+            unreachable: false,
+        }
+    }
+
+    fn unreachable() -> Assigned {
+        Assigned {
+            unreachable: true,
+            ..Assigned::default()
+        }
+    }
+}
+
+// States at every `break` and `continue` of one loop
+#[derive(Default)]
+struct LoopExits {
+    breaks: Vec<Assigned>,
+    continues: Vec<Assigned>,
+}
+
+fn join_all(first: Assigned, rest: &[Assigned]) -> Assigned {
+    rest.iter().fold(first, |joined, s| joined.join(s))
+}
+
+struct Checker {
+    // every scalar `int` local and where it was declared
+    locals: HashMap<String, Span>,
+    reported: HashSet<String>,
+    // exits of every enclosing loop, innermost last
+    loops: Vec<LoopExits>,
+    zero_init: bool,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Checker {
+    fn read(&mut self, name: &str, span: Span, state: &Assigned) {
+        let declared = match self.locals.get(name) {
+            Some(declared) => *declared,
+            None => return, // argument
+        };
+        if state.unreachable || state.definitely.contains(name) || !self.reported.insert(name.to_string()) {
+            return;
+        }
+
+        let (message, label) = if state.maybe.contains(name) {
+            (format!("`{}` may be used before it is assigned", name), "not assigned on every path to here")
+        } else {
+            (format!("`{}` is used before it is assigned", name), "read before any assignment")
+        };
+
+        let d = if self.zero_init {
+            Diagnostic::warning(E_USE_BEFORE_ASSIGNMENT, message, span)
+                .with_note("`--zero-init-locals` is set, so this reads 0")
+        } else {
+            Diagnostic::error(E_USE_BEFORE_ASSIGNMENT, message, span)
+                .with_note("locals start with whatever was on the stack; assign a value first or compile with `--zero-init-locals`")
+        };
+        self.diagnostics.push(
+            d.with_label(label)
+                .with_secondary(declared, "declared here without a value"),
+        );
+    }
+
+    fn check_stmts(&mut self, statements: &[Statement], state: &mut Assigned) {
+        for s in statements {
+            self.check_stmt(s, state);
+        }
+    }
+
+    // Checks one pass through a loop body starting in `state`;
+    // returns the state at its end and at its `break`s and `continue`s
+    fn check_loop_body(&mut self, body: &[Statement], mut state: Assigned) -> (Assigned, LoopExits) {
+        self.loops.push(LoopExits::default());
+        self.check_stmts(body, &mut state);
+        let exits = self.loops.pop().unwrap_or_default();
+        (state, exits)
+    }
+
+    // State after a loop left either when `condition` fails in `at_cond`
+    // or through one of `breaks`
+    fn loop_exit(condition: &BooleanExpression, at_cond: &Assigned, breaks: &[Assigned]) -> Assigned {
+        // Leaving through the condition is impossible for `true`
+        let through_condition = if matches!(condition, BooleanExpression::True(_)) {
+            Assigned::unreachable()
+        } else {
+            at_cond.clone()
+        };
+        join_all(through_condition, breaks)
+    }
+
+    fn check_stmt(&mut self, s: &Statement, state: &mut Assigned) {
+        match s {
+            Statement::Assign { name, expression, .. } => {
+                self.check_expr(expression, state);
+                state.insert(name);
+            }
+
+            Statement::AssignElement { index, expression, .. } => {
+                self.check_expr(index, state);
+                self.check_expr(expression, state);
+            }
+
+            Statement::If {
+                condition,
+                then_statements,
+                else_statements,
+                ..
+            } => {
+                self.check_bool(condition, state);
+
+                let mut then_state = state.clone();
+                self.check_stmts(then_statements, &mut then_state);
+                let mut else_state = state.clone();
+                self.check_stmts(else_statements, &mut else_state);
+
+                *state = then_state.join(&else_state);
+            }
+
+            Statement::While { condition, body, .. } => {
+                // From the second iteration on, anything the body assigns
+                // may already hold a value when the condition runs again
+                let mut carried = HashSet::new();
+                assigned_in(body, &mut carried);
+                state.maybe.extend(carried);
+
+                self.check_bool(condition, state);
+
+                let (_, exits) = self.check_loop_body(body, state.clone());
+                *state = Self::loop_exit(condition, state, &exits.breaks);
+            }
+
+            Statement::For {
+                init,
+                condition,
+                step,
+                body,
+                ..
+            } => {
+                if let Some(init) = init {
+                    self.check_stmt(init, state);
+                }
+
+                let mut carried = HashSet::new();
+                assigned_in(body, &mut carried);
+                if let Some(step) = step {
+                    assigned_in(std::slice::from_ref(&**step), &mut carried);
+                }
+                state.maybe.extend(carried);
+
+                self.check_bool(condition, state);
+
+                let (end, exits) = self.check_loop_body(body, state.clone());
+                if let Some(step) = step {
+                    // the step runs after the body and after every `continue`
+                    let mut at_step = join_all(end, &exits.continues);
+                    self.check_stmt(step, &mut at_step);
+                }
+                *state = Self::loop_exit(condition, state, &exits.breaks);
+            }
+
+            Statement::DoWhile { body, condition, .. } => {
+                let mut carried = HashSet::new();
+                assigned_in(body, &mut carried);
+                state.maybe.extend(carried);
+
+                let (end, exits) = self.check_loop_body(body, state.clone());
+                let at_cond = join_all(end, &exits.continues);
+                self.check_bool(condition, &at_cond);
+                *state = Self::loop_exit(condition, &at_cond, &exits.breaks);
+            }
+
+            Statement::Call { expression, .. } => self.check_expr(expression, state),
+
+            Statement::Return { expression, .. } => {
+                self.check_expr(expression, state);
+                *state = Assigned::unreachable();
+            }
+
+            Statement::Break { .. } => {
+                if let Some(exits) = self.loops.last_mut() {
+                    exits.breaks.push(state.clone());
+                }
+                *state = Assigned::unreachable();
+            }
+
+            Statement::Continue { .. } => {
+                if let Some(exits) = self.loops.last_mut() {
+                    exits.continues.push(state.clone());
+                }
+                *state = Assigned::unreachable();
+            }
+        }
+    }
+
+    fn check_bool(&mut self, b: &BooleanExpression, state: &Assigned) {
+        use BooleanExpression::*;
+
+        match b {
+            True(_) | False(_) => {}
+            CompareLessThan(l, r, _)
+            | CompareLessThanEqualTo(l, r, _)
+            | CompareGreaterThan(l, r, _)
+            | CompareGreaterThanEqualTo(l, r, _)
+            | CompareEqualTo(l, r, _)
+            | CompareNotEqualTo(l, r, _) => {
+                self.check_expr(l, state);
+                self.check_expr(r, state);
+            }
+            And(l, r, _) | Or(l, r, _) => {
+                self.check_bool(l, state);
+                self.check_bool(r, state);
+            }
+            Not(inner, _) => self.check_bool(inner, state),
+        }
+    }
+
+    fn check_expr(&mut self, node: &Rc<RefCell<ASTNode>>, state: &Assigned) {
+        use ASTNode::*;
+
+        match &*node.borrow() {
+            Number(_, _) => {}
+            Identifier(name, span) => self.read(name, *span, state),
+            Index(_, index, _) => self.check_expr(index, state),
+            Neg(operand, _) | BitNot(operand, _) => self.check_expr(operand, state),
+            Condition(b, _) => self.check_bool(b, state),
+            Call(_, args, _) => {
+                for arg in args {
+                    self.check_expr(arg, state);
+                }
+            }
+            Add(l, r, _)
+            | Sub(l, r, _)
+            | Multiply(l, r, _)
+            | Divide(l, r, _)
+            | Modulo(l, r, _)
+            | BitAnd(l, r, _)
+            | BitOr(l, r, _)
+            | BitXor(l, r, _)
+            | ShiftLeft(l, r, _)
+            | ShiftRight(l, r, _)
+            | ShiftRightLogical(l, r, _) => {
+                self.check_expr(l, state);
+                self.check_expr(r, state);
+            }
+        }
+    }
+}
+
+// Every name assigned anywhere in `statements`
+fn assigned_in(statements: &[Statement], names: &mut HashSet<String>) {
+    for s in statements {
+        match s {
+            Statement::Assign { name, .. } => {
+                names.insert(name.clone());
+            }
+            Statement::If {
+                then_statements,
+                else_statements,
+                ..
+            } => {
+                assigned_in(then_statements, names);
+                assigned_in(else_statements, names);
+            }
+            Statement::While { body, .. } | Statement::DoWhile { body, .. } => assigned_in(body, names),
+            Statement::For { init, step, body, .. } => {
+                for s in init.iter().chain(step.iter()) {
+                    assigned_in(std::slice::from_ref(&**s), names);
+                }
+                assigned_in(body, names);
+            }
+            Statement::AssignElement { .. }
+            | Statement::Break { .. }
+            | Statement::Continue { .. }
+            | Statement::Return { .. }
+            | Statement::Call { .. } => {}
+        }
+    }
+}
+
+/// Reports reads of `int` locals that may happen before any assignment
+/// (errors normally, warnings when locals are zero-initialised) and
+/// paths that reach the end of a function without returning.
+pub fn check_definite_assignment(p: &Program, zero_init: bool) -> Vec<Diagnostic> {
+    p.functions.iter().flat_map(|f| check_function(f, zero_init)).collect()
+}
+
+fn check_function(f: &Function, zero_init: bool) -> Vec<Diagnostic> {
+    let mut checker = Checker {
+        locals: f
+            .var_declarations
+            .iter()
+            .filter(|v| v.length.is_none())
+            .map(|v| (v.name.clone(), v.span))
+            .collect(),
+        reported: HashSet::new(),
+        loops: Vec::new(),
+        zero_init,
+        diagnostics: Vec::new(),
+    };
+
+    let mut state = Assigned::default();
+    checker.check_stmts(&f.statements, &mut state);
+    if !state.unreachable {
+        checker.diagnostics.push(
+            Diagnostic::error(E_MISSING_RETURN, format!("not every path through `{}` returns a value", f.name), f.end_span)
+                .with_label(format!("control can reach the end of `{}` here", f.name))
+                .with_note("end every path with `return EXPR;`"),
+        );
+    }
+
+    checker.diagnostics
+}
@@ -0,0 +1,482 @@
+use crate::ast::{ASTNode, BooleanExpression, Function, Program, Statement};
+use crate::diagnostics::{Diagnostic, Severity, W_DEAD_STORE, W_UNUSED_ARGUMENT, W_UNUSED_VARIABLE};
+use crate::span::Span;
+use std::cell::RefCell;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+
+// ===============================================================
+// Lints
+// Warnings about programs that compile fine but probably do not do
+// what was meant. Each lint has a name used by the command line:
+//   -W<name>        report it as a warning (the default)
+//   -Wno-<name>     do not report it
+//   -Werror=<name>  report it as an error
+//   -Werror         report every warning as an error
+// ===============================================================
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Lint {
+    UnusedVariable,
+    UnusedArgument,
+    DeadStore,
+}
+
+const ALL_LINTS: [Lint; 3] = [Lint::UnusedVariable, Lint::UnusedArgument, Lint::DeadStore];
+
+impl Lint {
+    pub fn name(self) -> &'static str {
+        match self {
+            Lint::UnusedVariable => "unused-variable",
+            Lint::UnusedArgument => "unused-argument",
+            Lint::DeadStore => "dead-store",
+        }
+    }
+
+    fn code(self) -> &'static str {
+        match self {
+            Lint::UnusedVariable => W_UNUSED_VARIABLE,
+            Lint::UnusedArgument => W_UNUSED_ARGUMENT,
+            Lint::DeadStore => W_DEAD_STORE,
+        }
+    }
+
+    fn from_name(name: &str) -> Option<Lint> {
+        ALL_LINTS.iter().copied().find(|l| l.name() == name)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Level {
+    Allow,
+    Warn,
+    Deny,
+}
+
+/// Which lints run and how loudly, built from `-W` flags.
+#[derive(Debug, Clone)]
+pub struct LintLevels {
+    levels: HashMap<Lint, Level>,
+    warnings_are_errors: bool,
+}
+
+impl Default for LintLevels {
+    fn default() -> Self {
+        LintLevels {
+            levels: ALL_LINTS.iter().map(|l| (*l, Level::Warn)).collect(),
+            warnings_are_errors: false,
+        }
+    }
+}
+
+impl LintLevels {
+    /// Applies one flag with its leading `-W` removed,
+    /// e.g. `error`, `no-dead-store` or `error=unused-variable`.
+    pub fn apply_flag(&mut self, flag: &str) -> Result<(), String> {
+        if flag == "error" {
+            self.warnings_are_errors = true;
+            return Ok(());
+        }
+
+        let (name, level) = if let Some(name) = flag.strip_prefix("error=") {
+            (name, Level::Deny)
+        } else if let Some(name) = flag.strip_prefix("no-") {
+            (name, Level::Allow)
+        } else {
+            (flag, Level::Warn)
+        };
+
+        match Lint::from_name(name) {
+            Some(lint) => {
+                self.levels.insert(lint, level);
+                Ok(())
+            }
+            None => {
+                let known: Vec<&str> = ALL_LINTS.iter().map(|l| l.name()).collect();
+                Err(format!("unknown warning `{}` (expected one of: {})", name, known.join(", ")))
+            }
+        }
+    }
+
+    fn level(&self, lint: Lint) -> Level {
+        self.levels.get(&lint).copied().unwrap_or(Level::Warn)
+    }
+
+    /// Under `-Werror`, turns every remaining warning into an error.
+    pub fn promote(&self, diagnostics: &mut [Diagnostic]) {
+        if !self.warnings_are_errors {
+            return;
+        }
+        for d in diagnostics.iter_mut().filter(|d| d.severity == Severity::Warning) {
+            d.severity = Severity::Error;
+            d.notes.push("warnings are treated as errors because of `-Werror`".to_string());
+        }
+    }
+}
+
+struct Linter<'a> {
+    levels: &'a LintLevels,
+    // (live where `continue` lands, live after the loop) for every
+    // enclosing loop, innermost last
+    loops: Vec<(HashSet<String>, HashSet<String>)>,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl Linter<'_> {
+    fn enabled(&self, lint: Lint) -> bool {
+        self.levels.level(lint) != Level::Allow
+    }
+
+    fn emit(&mut self, lint: Lint, message: String, span: Span, label: &str) -> &mut Diagnostic {
+        let d = match self.levels.level(lint) {
+            Level::Deny => Diagnostic::error(lint.code(), message, span)
+                .with_note(format!("`-Werror={}` is set", lint.name())),
+            _ => Diagnostic::warning(lint.code(), message, span),
+        }
+        .with_label(label);
+        self.diagnostics.push(d);
+        self.diagnostics.last_mut().unwrap()
+    }
+
+    // -----------------------------------------------------------
+    // Unused declarations: never read anywhere in their function
+    // -----------------------------------------------------------
+    fn check_unused(&mut self, f: &Function, read: &HashSet<String>, written: &HashSet<String>) {
+        if self.enabled(Lint::UnusedArgument) {
+            for a in f.arg_declarations.iter().filter(|a| !read.contains(&a.name)) {
+                self.emit(Lint::UnusedArgument, format!("unused argument `{}`", a.name), a.span, "never read")
+                    .notes
+                    .push("arguments are matched by position; removing it changes every caller".to_string());
+            }
+        }
+
+        if self.enabled(Lint::UnusedVariable) {
+            for v in f.var_declarations.iter().filter(|v| !read.contains(&v.name)) {
+                let (message, label) = if written.contains(&v.name) {
+                    (format!("variable `{}` is assigned but never read", v.name), "only ever assigned")
+                } else {
+                    (format!("unused variable `{}`", v.name), "declared but never used")
+                };
+                self.emit(Lint::UnusedVariable, message, v.span, label);
+            }
+        }
+    }
+
+    // -----------------------------------------------------------
+    // Dead stores: backward liveness over the statement tree.
+    // `live` holds the names whose current value may still be read.
+    // -----------------------------------------------------------
+    fn live_before_stmts(
+        &mut self,
+        statements: &[Statement],
+        live: HashSet<String>,
+        report: bool,
+        read: &HashSet<String>,
+    ) -> HashSet<String> {
+        statements
+            .iter()
+            .rev()
+            .fold(live, |live, s| self.live_before_stmt(s, live, report, read))
+    }
+
+    fn live_before_stmt(
+        &mut self,
+        s: &Statement,
+        mut live: HashSet<String>,
+        report: bool,
+        read: &HashSet<String>,
+    ) -> HashSet<String> {
+        match s {
+            Statement::Assign { name, name_span, expression, .. } => {
+                // Names never read at all are already covered by the unused lints
+                if report && !live.contains(name) && read.contains(name) {
+                    self.emit(
+                        Lint::DeadStore,
+                        format!("value assigned to `{}` is never read", name),
+                        *name_span,
+                        "overwritten or returned before it is read",
+                    );
+                }
+                live.remove(name);
+                reads_in_expr(expression, &mut live);
+                live
+            }
+
+            // Storing one element leaves the others live, so it is never dead
+            // and does not end the array's liveness
+            Statement::AssignElement { index, expression, .. } => {
+                reads_in_expr(index, &mut live);
+                reads_in_expr(expression, &mut live);
+                live
+            }
+
+            Statement::Call { expression, .. } => {
+                reads_in_expr(expression, &mut live);
+                live
+            }
+
+            Statement::If {
+                condition,
+                then_statements,
+                else_statements,
+                ..
+            } => {
+                let mut before = self.live_before_stmts(then_statements, live.clone(), report, read);
+                before.extend(self.live_before_stmts(else_statements, live, report, read));
+                reads_in_bool(condition, &mut before);
+                before
+            }
+
+            Statement::While { condition, body, .. } => {
+                self.live_before_top_tested_loop(condition, None, body, live, report, read)
+            }
+
+            Statement::For {
+                init,
+                condition,
+                step,
+                body,
+                ..
+            } => {
+                let at_cond = self.live_before_top_tested_loop(condition, step.as_deref(), body, live, report, read);
+                match init {
+                    Some(init) => self.live_before_stmt(init, at_cond, report, read),
+                    None => at_cond,
+                }
+            }
+
+            Statement::DoWhile { body, condition, .. } => {
+                // The body runs first and again after every true condition
+                let at_cond = |entry: &HashSet<String>| {
+                    let mut at_cond = live.clone();
+                    at_cond.extend(entry.iter().cloned());
+                    reads_in_bool(condition, &mut at_cond);
+                    at_cond
+                };
+                let mut entry = HashSet::new();
+                loop {
+                    let next = self.live_through_loop_body(None, body, at_cond(&entry), &live, false, read);
+                    if next == entry {
+                        break;
+                    }
+                    entry = next;
+                }
+                if report {
+                    self.live_through_loop_body(None, body, at_cond(&entry), &live, true, read);
+                }
+                entry
+            }
+
+            // Whatever follows a jump is never reached, so what is live
+            // before it is what is live where it lands
+            Statement::Return { expression, .. } => {
+                let mut live = HashSet::new();
+                reads_in_expr(expression, &mut live);
+                live
+            }
+            Statement::Break { .. } => self.loops.last().map(|(_, exit)| exit.clone()).unwrap_or(live),
+            Statement::Continue { .. } => self.loops.last().map(|(cond, _)| cond.clone()).unwrap_or(live),
+        }
+    }
+}
+
+impl Linter<'_> {
+    // Live names at the condition of a `while` or `for`. The condition
+    // runs again after the body, so iterate until they stop growing.
+    fn live_before_top_tested_loop(
+        &mut self,
+        condition: &BooleanExpression,
+        step: Option<&Statement>,
+        body: &[Statement],
+        live: HashSet<String>,
+        report: bool,
+        read: &HashSet<String>,
+    ) -> HashSet<String> {
+        let mut at_cond = live.clone();
+        reads_in_bool(condition, &mut at_cond);
+        loop {
+            let mut next = self.live_through_loop_body(step, body, at_cond.clone(), &live, false, read);
+            next.extend(live.iter().cloned());
+            reads_in_bool(condition, &mut next);
+            if next == at_cond {
+                break;
+            }
+            at_cond = next;
+        }
+        if report {
+            self.live_through_loop_body(step, body, at_cond.clone(), &live, true, read);
+        }
+        at_cond
+    }
+
+    // Live names on entry to one pass through `body` (then `step`), given
+    // what is live at the condition that follows and after the loop
+    fn live_through_loop_body(
+        &mut self,
+        step: Option<&Statement>,
+        body: &[Statement],
+        at_cond: HashSet<String>,
+        exit: &HashSet<String>,
+        report: bool,
+        read: &HashSet<String>,
+    ) -> HashSet<String> {
+        let at_step = match step {
+            Some(step) => self.live_before_stmt(step, at_cond, report, read),
+            None => at_cond,
+        };
+        self.loops.push((at_step.clone(), exit.clone()));
+        let entry = self.live_before_stmts(body, at_step, report, read);
+        self.loops.pop();
+        entry
+    }
+}
+
+fn reads_in_expr(node: &Rc<RefCell<ASTNode>>, names: &mut HashSet<String>) {
+    use ASTNode::*;
+
+    match &*node.borrow() {
+        Number(_, _) => {}
+        Identifier(name, _) => {
+            names.insert(name.clone());
+        }
+        Index(name, index, _) => {
+            names.insert(name.clone());
+            reads_in_expr(index, names);
+        }
+        Neg(operand, _) | BitNot(operand, _) => reads_in_expr(operand, names),
+        Condition(b, _) => reads_in_bool(b, names),
+        Call(_, args, _) => {
+            for arg in args {
+                reads_in_expr(arg, names);
+            }
+        }
+        Add(l, r, _)
+        | Sub(l, r, _)
+        | Multiply(l, r, _)
+        | Divide(l, r, _)
+        | Modulo(l, r, _)
+        | BitAnd(l, r, _)
+        | BitOr(l, r, _)
+        | BitXor(l, r, _)
+        | ShiftLeft(l, r, _)
+        | ShiftRight(l, r, _)
+        | ShiftRightLogical(l, r, _) => {
+            reads_in_expr(l, names);
+            reads_in_expr(r, names);
+        }
+    }
+}
+
+fn reads_in_bool(b: &BooleanExpression, names: &mut HashSet<String>) {
+    use BooleanExpression::*;
+
+    match b {
+        True(_) | False(_) => {}
+        CompareLessThan(l, r, _)
+        | CompareLessThanEqualTo(l, r, _)
+        | CompareGreaterThan(l, r, _)
+        | CompareGreaterThanEqualTo(l, r, _)
+        | CompareEqualTo(l, r, _)
+        | CompareNotEqualTo(l, r, _) => {
+            reads_in_expr(l, names);
+            reads_in_expr(r, names);
+        }
+        And(l, r, _) | Or(l, r, _) => {
+            reads_in_bool(l, names);
+            reads_in_bool(r, names);
+        }
+        Not(inner, _) => reads_in_bool(inner, names),
+    }
+}
+
+// Every name read and every name written anywhere in `statements`
+fn collect_uses(statements: &[Statement], read: &mut HashSet<String>, written: &mut HashSet<String>) {
+    for s in statements {
+        match s {
+            Statement::Assign { name, expression, .. } => {
+                reads_in_expr(expression, read);
+                written.insert(name.clone());
+            }
+            Statement::AssignElement { name, index, expression, .. } => {
+                reads_in_expr(index, read);
+                reads_in_expr(expression, read);
+                written.insert(name.clone());
+            }
+            Statement::If {
+                condition,
+                then_statements,
+                else_statements,
+                ..
+            } => {
+                reads_in_bool(condition, read);
+                collect_uses(then_statements, read, written);
+                collect_uses(else_statements, read, written);
+            }
+            Statement::While { condition, body, .. } => {
+                reads_in_bool(condition, read);
+                collect_uses(body, read, written);
+            }
+            Statement::For {
+                init,
+                condition,
+                step,
+                body,
+                ..
+            } => {
+                for s in init.iter().chain(step.iter()) {
+                    collect_uses(std::slice::from_ref(&**s), read, written);
+                }
+                reads_in_bool(condition, read);
+                collect_uses(body, read, written);
+            }
+            Statement::DoWhile { body, condition, .. } => {
+                collect_uses(body, read, written);
+                reads_in_bool(condition, read);
+            }
+            Statement::Return { expression, .. } | Statement::Call { expression, .. } => {
+                reads_in_expr(expression, read)
+            }
+            Statement::Break { .. } | Statement::Continue { .. } => {}
+        }
+    }
+}
+
+/// Runs every enabled lint over `p`.
+pub fn lint_program(p: &Program, levels: &LintLevels) -> Vec<Diagnostic> {
+    let mut linter = Linter {
+        levels,
+        loops: Vec::new(),
+        diagnostics: Vec::new(),
+    };
+
+    for f in &p.functions {
+        let mut read = HashSet::new();
+        let mut written = HashSet::new();
+        collect_uses(&f.statements, &mut read, &mut written);
+
+        linter.check_unused(f, &read, &written);
+
+        if linter.enabled(Lint::DeadStore) {
+            // A store to a global may be read after `return` or by a callee,
+            // so only stores to the function's own names can be dead
+            let declared: HashSet<&String> = f.arg_declarations.iter().chain(&f.var_declarations).map(|d| &d.name).collect();
+            read.retain(|name| declared.contains(name));
+            linter.live_before_stmts(&f.statements, HashSet::new(), true, &read);
+        }
+    }
+
+    // In the order they are reported, so that the first warning of each
+    // lint is the one explaining which flag turns it off
+    let mut diagnostics = linter.diagnostics;
+    diagnostics.sort_by_key(|d| d.span.start);
+    if !levels.warnings_are_errors {
+        let mut explained = HashSet::new();
+        for d in diagnostics.iter_mut().filter(|d| d.severity == Severity::Warning) {
+            let lint = ALL_LINTS.iter().copied().find(|l| l.code() == d.code);
+            if let Some(lint) = lint.filter(|l| explained.insert(*l)) {
+                d.notes.insert(0, format!("`-W{}` is on by default; disable it with `-Wno-{}`", lint.name(), lint.name()));
+            }
+        }
+    }
+    diagnostics
+}
@@ -1,24 +1,82 @@
 use std::env;
 use std::fs;
+use std::process;
+use std::collections::HashSet;
 
+mod span;
+mod diagnostics;
 mod ast;
 mod scanner;
 mod parser;
+mod semantic;
+mod flow;
+mod lints;
 mod codegen;
 
-use crate::scanner::scan_source;
+use crate::scanner::{scan_source, SpannedToken};
 use crate::parser::Parser;
-use crate::codegen::generate_program_x86;
+use crate::semantic::check_program;
+use crate::flow::check_definite_assignment;
+use crate::lints::{lint_program, LintLevels};
+use crate::codegen::{generate_program_x86, CodegenOptions, DivisionByZero};
+use crate::diagnostics::{has_errors, report, Diagnostic, ErrorFormat, E_INVALID_CHARACTER, E_UNTERMINATED_COMMENT};
+
+// Where the token after each skipped character or unterminated comment
+// starts. A syntax error there is the scanner's error seen again, so
+// only the scanner's is reported.
+fn cascade_starts(tokens: &[SpannedToken], lexical: &[Diagnostic]) -> HashSet<usize> {
+    lexical
+        .iter()
+        .filter(|d| d.code == E_INVALID_CHARACTER || d.code == E_UNTERMINATED_COMMENT)
+        .filter_map(|d| tokens.iter().find(|t| t.span.start >= d.span.end))
+        .map(|t| t.span.start)
+        .collect()
+}
+
+fn usage_error(message: &str) -> ! {
+    eprintln!("error: {}", message);
+    eprintln!("usage: rucompiler-x86-second [--error-format=human|json] [--zero-init-locals] [--div-zero=trap|undefined] [--bounds-check] [-W<flag>...] <input.rucomp>");
+    process::exit(2);
+}
 
 fn main() {
-    // Expect exactly one argument: the .rucomp file
-    let args: Vec<String> = env::args().collect();
-    if args.len() != 2 {
-        eprintln!("usage: rucompiler-x86-second <input.rucomp>");
-        return;
+    // Expect the .rucomp file, optionally preceded or followed by flags
+    let mut input_file: Option<String> = None;
+    let mut error_format = ErrorFormat::Human;
+    let mut options = CodegenOptions::default();
+    let mut lint_levels = LintLevels::default();
+
+    for arg in env::args().skip(1) {
+        if let Some(value) = arg.strip_prefix("--error-format=") {
+            match ErrorFormat::from_flag(value) {
+                Some(format) => error_format = format,
+                None => usage_error(&format!("unknown error format `{}` (expected `human` or `json`)", value)),
+            }
+        } else if let Some(value) = arg.strip_prefix("--div-zero=") {
+            match DivisionByZero::from_flag(value) {
+                Some(policy) => options.div_zero = policy,
+                None => usage_error(&format!("unknown division-by-zero policy `{}` (expected `trap` or `undefined`)", value)),
+            }
+        } else if arg == "--zero-init-locals" {
+            options.zero_init_locals = true;
+        } else if arg == "--bounds-check" {
+            options.bounds_check = true;
+        } else if let Some(flag) = arg.strip_prefix("-W") {
+            if let Err(message) = lint_levels.apply_flag(flag) {
+                usage_error(&message);
+            }
+        } else if arg.starts_with('-') || input_file.is_some() {
+            usage_error(&format!("unexpected argument `{}`", arg));
+        } else {
+            input_file = Some(arg);
+        }
     }
 
-    let input_file = &args[1];
+    let input_file = match input_file {
+        Some(file) => file,
+        None => usage_error("missing input file"),
+    };
+    let input_file = &input_file;
 
     // Derive output filename: *.rucomp -> *.s
     let output_file = if let Some(stripped) = input_file.strip_suffix(".rucomp") {
@@ -31,17 +89,50 @@ fn main() {
     let source = fs::read_to_string(input_file)
         .expect("could not read input file");
 
-    // 1) Scan source into tokens
-    let tokens = scan_source(&source);
+    // 1) Scan source into tokens; lexical errors are reported together
+    //    with the syntax errors in the tokens that remain
+    let (tokens, mut diagnostics) = scan_source(&source);
+    let cascades = cascade_starts(&tokens, &diagnostics);
 
-    // 2) Parse the full program (args, vars, statements, return)
+    // 2) Parse the full program (args, vars, statements)
     let mut parser = Parser::new(tokens);
-    let program = parser.parse_program();
+    let program = match parser.parse_program() {
+        Ok(program) if diagnostics.is_empty() => program,
+        result => {
+            let syntax = result.err().unwrap_or_default();
+            diagnostics.extend(syntax.into_iter().filter(|d| !cascades.contains(&d.span.start)));
+            diagnostics.sort_by_key(|d| d.span.start);
+            report(&diagnostics, input_file, &source, error_format);
+            process::exit(1);
+        }
+    };
+
+    // 3) Resolve every name against the declarations
+    if let Err(diagnostics) = check_program(&program) {
+        report(&diagnostics, input_file, &source, error_format);
+        process::exit(1);
+    }
 
-    // 3) Generate x86-64 assembly for the full program
-    let asm = generate_program_x86(&program);
+    // 4) Check that no local is read before it is assigned, then lint
+    let mut diagnostics = check_definite_assignment(&program, options.zero_init_locals);
+    diagnostics.extend(lint_program(&program, &lint_levels));
+    lint_levels.promote(&mut diagnostics);
+    diagnostics.sort_by_key(|d| d.span.start);
+    report(&diagnostics, input_file, &source, error_format);
+    if has_errors(&diagnostics) {
+        process::exit(1);
+    }
+
+    // 5) Generate x86-64 assembly for the full program
+    let asm = match generate_program_x86(&program, &options) {
+        Ok(asm) => asm,
+        Err(diagnostics) => {
+            report(&diagnostics, input_file, &source, error_format);
+            process::exit(1);
+        }
+    };
 
-    // 4) Write the .s file
+    // 6) Write the .s file
     fs::write(&output_file, asm)
         .expect("failed to write output file");
 }
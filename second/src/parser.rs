@@ -1,55 +1,465 @@
-use crate::scanner::Token;
-use crate::ast::{ASTNode, BooleanExpression, Statement, Program};
+use crate::scanner::{SpannedToken, Token};
+use crate::ast::{
+    ASTNode, BooleanExpression, Declaration, ExternFunction, Function, GlobalVariable, Statement, Program,
+};
+use crate::diagnostics::{
+    Diagnostic, E_LITERAL_TOO_LARGE, E_UNEXPECTED_TOKEN, E_UNMATCHED_BRACE,
+};
+use crate::span::Span;
 use std::cell::RefCell;
 use std::rc::Rc;
 
+// Every production either returns its node or the first error it hit;
+// parse_program() collects those errors and resynchronises.
+type ParseResult<T> = Result<T, Box<Diagnostic>>;
+
 pub struct Parser {
-    token_stream: Vec<Token>,
+    token_stream: Vec<SpannedToken>,
     current_pos: usize,
+    diagnostics: Vec<Diagnostic>,
+    // inside a function body, where recovery may stop at a statement keyword
+    in_body: bool,
 }
 
 impl Parser {
-    pub fn new(tokens: Vec<Token>) -> Self {
+    pub fn new(tokens: Vec<SpannedToken>) -> Self {
         Parser {
             token_stream: tokens,
             current_pos: 0,
+            diagnostics: Vec::new(),
+            in_body: false,
         }
     }
 
     fn peek(&self) -> &Token {
         self.token_stream
             .get(self.current_pos)
+            .map(|t| &t.token)
+            .unwrap_or(&Token::EndOfFile)
+    }
+
+    // The token after the current one
+    fn peek_second(&self) -> &Token {
+        self.token_stream
+            .get(self.current_pos + 1)
+            .map(|t| &t.token)
             .unwrap_or(&Token::EndOfFile)
     }
 
+    // Whether the current token starts a top-level item
+    fn at_item_start(&self) -> bool {
+        matches!(
+            self.peek(),
+            Token::KeywordFn | Token::KeywordExtern | Token::KeywordGlobal | Token::KeywordExport
+        )
+    }
+
+    // Span of the current token (the EOF token once the stream is exhausted)
+    fn peek_span(&self) -> Span {
+        self.token_stream
+            .get(self.current_pos)
+            .or(self.token_stream.last())
+            .map(|t| t.span)
+            .unwrap_or_default()
+    }
+
+    // Span of the most recently consumed token
+    fn previous_span(&self) -> Span {
+        self.current_pos
+            .checked_sub(1)
+            .and_then(|i| self.token_stream.get(i))
+            .map(|t| t.span)
+            .unwrap_or_else(|| self.peek_span())
+    }
+
+    // Zero-width span just after the most recently consumed token
+    fn after_previous(&self) -> Span {
+        let prev = self.previous_span();
+        Span::new(prev.end, prev.end, prev.line, prev.column + (prev.end - prev.start))
+    }
+
     fn consume(&mut self) {
         if self.current_pos < self.token_stream.len() {
             self.current_pos += 1;
         }
     }
 
-    fn expect(&mut self, expected: &Token) {
+    // "expected X, found Y" at the current token
+    fn unexpected(&self, expected: &str) -> Diagnostic {
+        Diagnostic::error(
+            E_UNEXPECTED_TOKEN,
+            format!("expected {}, found {}", expected, self.peek()),
+            self.peek_span(),
+        )
+        .with_label(format!("expected {}", expected))
+    }
+
+    fn expect(&mut self, expected: &Token) -> ParseResult<()> {
         if self.peek() == expected {
             self.consume();
+            return Ok(());
+        }
+
+        let mut d = self.unexpected(&expected.to_string());
+
+        let prev = self.previous_span();
+        let after_prev = self.after_previous();
+
+        // A missing `;` or `}` is best shown right after the line it belongs
+        // to, not at whatever happens to start the next line.
+        let next_line = self.current_pos > 0 && self.peek_span().line > prev.line;
+        if next_line {
+            d.span = after_prev;
+        }
+
+        // Closing punctuation that is simply missing can be inserted as-is
+        let at_close = matches!(self.peek(), Token::RightBrace | Token::EndOfFile);
+        let closing = matches!(expected, Token::Semicolon | Token::RightParen | Token::RightBrace);
+        if closing && (next_line || at_close) {
+            let text = expected.to_string().trim_matches('`').to_string();
+            d = d.with_suggestion(format!("insert {}", expected), after_prev, text);
+        }
+        Err(d.into())
+    }
+
+    // Like expect(), but points back at the token that opened the construct
+    fn expect_closing(&mut self, expected: &Token, opened_at: Span, what: &str) -> ParseResult<()> {
+        self.expect(expected)
+            .map_err(|d| Box::new(d.with_secondary(opened_at, format!("{} opened here", what))))
+    }
+
+    fn expect_identifier(&mut self, context: &str) -> ParseResult<Declaration> {
+        if let Token::Identifier(name) = self.peek().clone() {
+            let span = self.peek_span();
+            self.consume();
+            Ok(Declaration { name, span, length: None })
         } else {
-            panic!("Syntax Error: expected {:?}, found {:?}", expected, self.peek());
+            Err(self.unexpected(&format!("identifier {}", context)).into())
+        }
+    }
+
+    // ============================================================
+    // ERROR RECOVERY
+    // Skip ahead to a point where parsing can sensibly resume:
+    // just past a `;` (not one inside `for (...)`), just past a `}` that
+    // closes a block opened while skipping, just before a `}` that
+    // closes an enclosing block, or just before the next top-level item.
+    // Inside a function body it also stops before a keyword that starts
+    // a statement, so a missing `;` does not swallow the next statement.
+    // ============================================================
+    fn synchronize(&mut self) {
+        let mut depth = 0usize;
+        let mut parens = 0usize;
+
+        loop {
+            match self.peek() {
+                Token::EndOfFile => return,
+                _ if self.at_item_start() => return,
+                Token::KeywordIf
+                | Token::KeywordWhile
+                | Token::KeywordFor
+                | Token::KeywordDo
+                | Token::KeywordReturn
+                | Token::KeywordBreak
+                | Token::KeywordContinue
+                | Token::KeywordInt
+                    if self.in_body && depth == 0 && parens == 0 =>
+                {
+                    return
+                }
+                Token::Semicolon if depth == 0 && parens == 0 => {
+                    self.consume();
+                    return;
+                }
+                Token::LeftParen => parens += 1,
+                Token::RightParen => parens = parens.saturating_sub(1),
+                Token::LeftBrace => {
+                    depth += 1;
+                    parens = 0;
+                }
+                Token::RightBrace => {
+                    if depth == 0 {
+                        return;
+                    }
+                    depth -= 1;
+                    if depth == 0 {
+                        self.consume();
+                        // `} else {` continues the same statement
+                        if !matches!(self.peek(), Token::KeywordElse) {
+                            return;
+                        }
+                        continue;
+                    }
+                }
+                _ => {}
+            }
+            self.consume();
+        }
+    }
+
+    // Record `result`'s error (if any), resynchronise and fall back to `default`
+    fn recover<T>(&mut self, result: ParseResult<T>, default: T) -> T {
+        match result {
+            Ok(value) => value,
+            Err(diagnostic) => {
+                self.diagnostics.push(*diagnostic);
+                self.synchronize();
+                default
+            }
         }
     }
 
     // ============================================================
     // TOP-LEVEL: parse_program()
+    // PROGRAM → ITEM ITEMS | DECLARATIONS ARGDECL TYPEDECL STMTS
+    // ============================================================
+    pub fn parse_program(&mut self) -> Result<Program, Vec<Diagnostic>> {
+        let mut program = Program { globals: Vec::new(), externs: Vec::new(), functions: Vec::new() };
+
+        // `extern` and `global` declarations may also precede an unnamed function
+        while self.at_item_start() && !matches!(self.peek(), Token::KeywordFn) {
+            self.parse_declaration(&mut program);
+        }
+
+        if matches!(self.peek(), Token::KeywordFn) {
+            self.parse_items(&mut program);
+        } else {
+            let function = self.parse_unnamed_function();
+            program.functions.push(function);
+            if self.at_item_start() {
+                let d = self.unexpected("end of file")
+                    .with_note("a file that starts with `args` holds a single function; define every function with `fn` instead");
+                self.diagnostics.push(d);
+                self.parse_items(&mut program);
+            }
+        }
+
+        if !self.diagnostics.is_empty() {
+            return Err(std::mem::take(&mut self.diagnostics));
+        }
+
+        Ok(program)
+    }
+
+    // ============================================================
+    // ITEMS → ITEM ITEMS | ε
+    // ITEM → FUNCTION | DECLARATION
+    // ============================================================
+    fn parse_items(&mut self, program: &mut Program) {
+        loop {
+            match self.peek() {
+                Token::KeywordFn => {
+                    let function = self.parse_function();
+                    if let Some(function) = self.recover(function.map(Some), None) {
+                        program.functions.push(function);
+                    }
+                }
+
+                Token::KeywordExtern | Token::KeywordGlobal | Token::KeywordExport => self.parse_declaration(program),
+
+                Token::EndOfFile => break,
+
+                Token::RightBrace => {
+                    let d = Diagnostic::error(E_UNMATCHED_BRACE, "unmatched `}`", self.peek_span())
+                        .with_label("no open block to close");
+                    self.diagnostics.push(d);
+                    self.consume();
+                }
+
+                _ => {
+                    let d = self.unexpected("`fn`, `extern` or `global`")
+                        .with_note("everything outside a function body must be a `fn NAME(ARGS) { ... }` definition, an `extern fn NAME(ARGS);` declaration or a `global int NAME = VALUE;` variable");
+                    self.diagnostics.push(d);
+                    self.consume();
+                    self.synchronize();
+                }
+            }
+        }
+    }
+
+    // ============================================================
+    // DECLARATION → EXTERN | GLOBAL
+    // Recovers from its own errors and adds the result to `program`.
+    // ============================================================
+    fn parse_declaration(&mut self, program: &mut Program) {
+        if matches!(self.peek(), Token::KeywordExtern) {
+            let declaration = self.parse_extern();
+            if let Some(declaration) = self.recover(declaration.map(Some), None) {
+                program.externs.push(declaration);
+            }
+        } else {
+            let variable = self.parse_global();
+            if let Some(variable) = self.recover(variable.map(Some), None) {
+                program.globals.push(variable);
+            }
+        }
+    }
+
+    // ============================================================
+    // GLOBAL → [export] global int IDENTIFIER [= [-] NUMBER] ;
+    // Without a value the variable starts at 0.
+    // ============================================================
+    fn parse_global(&mut self) -> ParseResult<GlobalVariable> {
+        let exported = matches!(self.peek(), Token::KeywordExport);
+        if exported {
+            self.consume();
+        }
+        self.expect(&Token::KeywordGlobal)?;
+        self.expect(&Token::KeywordInt)?;
+        let name = self.expect_identifier("after `global int`")?;
+
+        let mut initial_value = 0;
+        if let Token::Assign = self.peek() {
+            self.consume();
+            let start = self.peek_span();
+            let negative = matches!(self.peek(), Token::Minus);
+            if negative {
+                self.consume();
+            }
+            let value = match *self.peek() {
+                Token::Number(value) => value,
+                _ => {
+                    return Err(Box::new(
+                        self.unexpected("number")
+                            .with_note("a global starts with a constant; assign anything else inside a function"),
+                    ))
+                }
+            };
+            self.consume();
+            initial_value = if negative { negate_literal(value, start.to(self.previous_span()))? } else { value };
+        }
+
+        self.expect(&Token::Semicolon)?;
+
+        Ok(GlobalVariable {
+            name: name.name,
+            span: name.span,
+            initial_value,
+            exported,
+        })
+    }
+
+    // ============================================================
+    // EXTERN → extern fn IDENTIFIER ( PARAMS ) ;
+    // ============================================================
+    fn parse_extern(&mut self) -> ParseResult<ExternFunction> {
+        self.expect(&Token::KeywordExtern)?;
+        self.expect(&Token::KeywordFn)?;
+        let name = self.expect_identifier("after `fn`")?;
+        let arg_declarations = self.parse_params()?;
+        self.expect(&Token::Semicolon)?;
+
+        Ok(ExternFunction {
+            name: name.name,
+            name_span: name.span,
+            arg_declarations,
+        })
+    }
+
+    // ============================================================
+    // FUNCTION → fn IDENTIFIER PARAMS { [TYPEDECL] STMTS }
     // ============================================================
-    pub fn parse_program(&mut self) -> Program {
-        let arg_declarations = self.parse_argdecl();
-        let var_declarations = self.parse_typedecl();
+    fn parse_function(&mut self) -> ParseResult<Function> {
+        self.expect(&Token::KeywordFn)?;
+        let name = self.expect_identifier("after `fn`")?;
+        let arg_declarations = self.parse_params()?;
+
+        let body_open = self.peek_span();
+        self.expect(&Token::LeftBrace)?;
+        self.in_body = true;
+
+        let var_declarations = if matches!(self.peek(), Token::KeywordInt) {
+            let vars = self.parse_typedecl();
+            self.recover(vars, Vec::new())
+        } else {
+            Vec::new()
+        };
+
         let statements = self.parse_statements();
-        let return_ident = self.parse_return();
+        self.in_body = false;
+
+        let end_span = self.peek_span();
+        self.expect_closing(&Token::RightBrace, body_open, "function body")?;
+
+        Ok(Function {
+            name: name.name,
+            name_span: name.span,
+            arg_declarations,
+            var_declarations,
+            statements,
+            end_span,
+        })
+    }
+
+    // ============================================================
+    // PARAMS → ( ) | ( IDENTIFIER PARAMSTAIL )
+    // PARAMSTAIL → ) | , IDENTIFIER PARAMSTAIL
+    // ============================================================
+    fn parse_params(&mut self) -> ParseResult<Vec<Declaration>> {
+        let open = self.peek_span();
+        self.expect(&Token::LeftParen)?;
+        let mut params = Vec::new();
+        if !matches!(self.peek(), Token::RightParen) {
+            params.push(self.expect_identifier("in parameter list")?);
+            while let Token::Comma = self.peek() {
+                self.consume();
+                params.push(self.expect_identifier("after `,`")?);
+            }
+        }
+        self.expect_closing(&Token::RightParen, open, "`(`")?;
+        Ok(params)
+    }
+
+    // ============================================================
+    // The original single-function form, compiled as `foo`
+    // ARGDECL TYPEDECL STMTS
+    // ============================================================
+    fn parse_unnamed_function(&mut self) -> Function {
+        let name_span = self.peek_span();
+        self.in_body = true;
+        let arg_declarations = if matches!(self.peek(), Token::KeywordArgs) {
+            let args = self.parse_argdecl();
+            self.recover(args, Vec::new())
+        } else {
+            let d = self.unexpected("`args` or `fn`")
+                .with_note("a program starts with `args NAME ...;` or with a `fn NAME(ARGS) { ... }` definition");
+            self.diagnostics.push(d);
+            Vec::new()
+        };
+
+        let var_declarations = if matches!(self.peek(), Token::KeywordInt) {
+            let vars = self.parse_typedecl();
+            self.recover(vars, Vec::new())
+        } else {
+            let d = self.unexpected("`int` declarations")
+                .with_note("local variables are declared after `args` with `int NAME, ...;`");
+            self.diagnostics.push(d);
+            Vec::new()
+        };
+
+        let mut statements = self.parse_statements();
+
+        // a `}` at the top level has no block to close
+        while matches!(self.peek(), Token::RightBrace) {
+            let d = Diagnostic::error(E_UNMATCHED_BRACE, "unmatched `}`", self.peek_span())
+                .with_label("no open block to close");
+            self.diagnostics.push(d);
+            self.consume();
+            statements.extend(self.parse_statements());
+        }
 
-        Program {
+        self.in_body = false;
+
+        // whether every path ends in `return` is checked by the flow pass
+        let end_span = self.after_previous();
+
+        Function {
+            name: "foo".to_string(),
+            name_span,
             arg_declarations,
             var_declarations,
             statements,
-            return_ident,
+            end_span,
         }
     }
 
@@ -57,81 +467,89 @@ impl Parser {
     // ARGDECL → args IDENTIFIER ARGDECLTAIL
     // ARGDECLTAIL → ; | IDENTIFIER ARGDECLTAIL
     // ============================================================
-    fn parse_argdecl(&mut self) -> Vec<String> {
+    fn parse_argdecl(&mut self) -> ParseResult<Vec<Declaration>> {
         let mut args = Vec::new();
 
-        match self.peek() {
-            Token::KeywordArgs => {
-                self.consume(); // consume 'args'
+        self.expect(&Token::KeywordArgs)?;
+        args.push(self.expect_identifier("after `args`")?);
 
-                if let Token::Identifier(name) = self.peek().clone() {
-                    args.push(name);
+        loop {
+            match self.peek() {
+                Token::Semicolon => {
                     self.consume();
-                } else {
-                    panic!("Expected identifier after 'args'");
+                    break;
                 }
-
-                loop {
-                    match self.peek() {
-                        Token::Semicolon => {
-                            self.consume();
-                            break;
-                        }
-                        Token::Identifier(name) => {
-                            args.push(name.clone());
-                            self.consume();
-                        }
-                        _ => panic!("Unexpected token in argument list: {:?}", self.peek()),
-                    }
+                Token::Identifier(name) => {
+                    args.push(Declaration { name: name.clone(), span: self.peek_span(), length: None });
+                    self.consume();
+                }
+                _ => {
+                    return Err(self.unexpected("identifier or `;` in argument list").into());
                 }
             }
-            _ => panic!("Program must start with 'args'"),
-        };
+        }
 
-        args
+        Ok(args)
     }
 
     // ============================================================
-    // TYPEDECL → int IDENTIFIER TYPEDECLTAIL
-    // TYPEDECLTAIL → ; | , IDENTIFIER TYPEDECLTAIL
+    // TYPEDECL → int VARDECL TYPEDECLTAIL
+    // TYPEDECLTAIL → ; | , VARDECL TYPEDECLTAIL
+    // VARDECL → IDENTIFIER | IDENTIFIER [ NUMBER ]
     // ============================================================
-    fn parse_typedecl(&mut self) -> Vec<String> {
+    fn parse_typedecl(&mut self) -> ParseResult<Vec<Declaration>> {
         let mut vars = Vec::new();
 
-        self.expect(&Token::KeywordInt);
+        self.expect(&Token::KeywordInt)?;
 
         // first identifier
-        if let Token::Identifier(name) = self.peek().clone() {
-            vars.push(name);
-            self.consume();
-        } else {
-            panic!("Expected identifier after 'int'");
-        }
+        vars.push(self.parse_vardecl("after `int`")?);
 
         loop {
             match self.peek() {
                 Token::Comma => {
                     self.consume();
-                    if let Token::Identifier(name) = self.peek().clone() {
-                        vars.push(name);
-                        self.consume();
-                    } else {
-                        panic!("Expected identifier after ','");
-                    }
+                    vars.push(self.parse_vardecl("after `,`")?);
                 }
                 Token::Semicolon => {
                     self.consume();
                     break;
                 }
-                _ => panic!("Unexpected token in type declaration: {:?}", self.peek()),
+                _ => {
+                    return Err(self.unexpected("`,` or `;` in type declaration").into());
+                }
             }
         }
 
-        vars
+        Ok(vars)
+    }
+
+    fn parse_vardecl(&mut self, context: &str) -> ParseResult<Declaration> {
+        let mut var = self.expect_identifier(context)?;
+        if let Token::LeftBracket = self.peek() {
+            let open = self.peek_span();
+            self.consume();
+            match *self.peek() {
+                Token::Number(length) => {
+                    self.consume();
+                    var.length = Some(length);
+                }
+                _ => {
+                    return Err(Box::new(
+                        self.unexpected("array length")
+                            .with_note("the length of an array is a number literal, e.g. `int a[16];`"),
+                    ))
+                }
+            }
+            self.expect_closing(&Token::RightBracket, open, "`[`")?;
+        }
+        Ok(var)
     }
 
     // ============================================================
     // STMTS → STMT STMTS | ε
+    // The list ends at `}`, the next top-level item or end of file; anything else that
+    // cannot start a statement is reported and skipped.
     // ============================================================
     fn parse_statements(&mut self) -> Vec<Statement> {
         let mut stmts = Vec::new();
@@ -140,12 +558,28 @@ impl Parser {
             match self.peek() {
                 Token::Identifier(_) |
                 Token::KeywordIf |
-                Token::KeywordWhile => {
-                    stmts.push(self.parse_statement());
+                Token::KeywordWhile |
+                Token::KeywordFor |
+                Token::KeywordDo |
+                Token::KeywordBreak |
+                Token::KeywordContinue |
+                Token::KeywordReturn => {
+                    let stmt = self.parse_statement();
+                    if let Some(stmt) = self.recover(stmt.map(Some), None) {
+                        stmts.push(stmt);
+                    }
                 }
 
-                // ε transition — statement list ends before return
-                _ => break,
+                // ε transition — statement list ends
+                Token::RightBrace | Token::EndOfFile => break,
+                _ if self.at_item_start() => break,
+
+                _ => {
+                    let d = self.unexpected("statement");
+                    self.diagnostics.push(d);
+                    self.consume();
+                    self.synchronize();
+                }
             }
         }
 
@@ -153,222 +587,663 @@ impl Parser {
     }
 
     // ============================================================
-    // STMT → ASSIGN | IFTHENELSE | WHILE
+    // STMT → ASSIGN | CALLSTMT | IFTHENELSE | WHILE | FOR | DOWHILE | JUMP | RET
     // ============================================================
-    fn parse_statement(&mut self) -> Statement {
+    fn parse_statement(&mut self) -> ParseResult<Statement> {
         match self.peek() {
+            Token::Identifier(_) if matches!(self.peek_second(), Token::LeftParen) => self.parse_call_statement(),
+
             Token::Identifier(_) => self.parse_assign(),
 
             Token::KeywordIf => self.parse_if_statement(),
 
             Token::KeywordWhile => self.parse_while_statement(),
 
-            _ => panic!("Invalid statement start: {:?}", self.peek()),
+            Token::KeywordFor => self.parse_for_statement(),
+
+            Token::KeywordDo => self.parse_do_while_statement(),
+
+            Token::KeywordBreak | Token::KeywordContinue => self.parse_loop_jump(),
+
+            Token::KeywordReturn => self.parse_return(),
+
+            _ => Err(self.unexpected("statement").into()),
         }
     }
 
     // ============================================================
-    // ASSIGN → IDENTIFIER = EXPR ;
+    // JUMP → break ; | continue ;
+    // (whether it is inside a loop is checked by the semantic pass)
+    // ============================================================
+    fn parse_loop_jump(&mut self) -> ParseResult<Statement> {
+        let start = self.peek_span();
+        let is_break = matches!(self.peek(), Token::KeywordBreak);
+        self.consume();
+        self.expect(&Token::Semicolon)?;
+
+        let span = start.to(self.previous_span());
+        Ok(if is_break { Statement::Break { span } } else { Statement::Continue { span } })
+    }
+
+    // ============================================================
+    // ASSIGN → IDENTIFIER = VALUE ; | IDENTIFIER OP= VALUE ;
+    //        | IDENTIFIER ++ ; | IDENTIFIER -- ;
+    //        | IDENTIFIER [ EXPR ] = VALUE ;
+    // `x OP= e` is stored as `x = x OP e`, `x++` as `x = x + 1`.
     // ============================================================
-    fn parse_assign(&mut self) -> Statement {
+    fn parse_assign(&mut self) -> ParseResult<Statement> {
+        let mut assign = self.parse_assignment()?;
+
+        // ;
+        self.expect(&Token::Semicolon)?;
+
+        if let Statement::Assign { span, .. } | Statement::AssignElement { span, .. } = &mut assign {
+            *span = span.to(self.previous_span());
+        }
+        Ok(assign)
+    }
+
+    // ASSIGN without the `;` (also the init and step of `for`)
+    fn parse_assignment(&mut self) -> ParseResult<Statement> {
+        let start = self.peek_span();
+
         // identifier
-        let name = if let Token::Identifier(name) = self.peek().clone() {
-            name
-        } else {
-            panic!("Expected identifier in assignment");
+        let target = self.expect_identifier("in assignment")?;
+        if let Token::LeftBracket = self.peek() {
+            return self.parse_element_assignment(target, start);
+        }
+        let current = || Rc::new(RefCell::new(ASTNode::Identifier(target.name.clone(), target.span)));
+
+        let compound = compound_operator(self.peek()).is_some();
+        let expr = match compound_operator(self.peek()) {
+            // =, then an expression or a condition stored as 0/1
+            None if matches!(self.peek(), Token::Assign) => {
+                self.consume();
+                self.parse_value()?
+            }
+            None => return Err(self.unexpected("`=` or another assignment operator").into()),
+
+            // ++ / --
+            Some(make) if matches!(self.peek(), Token::Increment | Token::Decrement) => {
+                let one = Rc::new(RefCell::new(ASTNode::Number(1, self.peek_span())));
+                self.consume();
+                combine(make, current(), one)
+            }
+
+            // OP=
+            Some(make) => {
+                self.consume();
+                let value = self.parse_value()?;
+                combine(make, current(), value)
+            }
         };
+
+        Ok(Statement::Assign {
+            name: target.name,
+            name_span: target.span,
+            expression: expr,
+            compound,
+            span: start.to(self.previous_span()),
+        })
+    }
+
+    // IDENTIFIER [ EXPR ] = VALUE, once the identifier has been read
+    fn parse_element_assignment(&mut self, target: Declaration, start: Span) -> ParseResult<Statement> {
+        let index = self.parse_subscript()?;
+        if !matches!(self.peek(), Token::Assign) {
+            let mut d = self.unexpected("`=`");
+            if compound_operator(self.peek()).is_some() {
+                d = d.with_note(format!(
+                    "array elements can only be assigned with `=`; write `{0}[i] = {0}[i] OP value;`",
+                    target.name
+                ));
+            }
+            return Err(d.into());
+        }
         self.consume();
+        let expression = self.parse_value()?;
 
-        // =
-        self.expect(&Token::Assign);
+        Ok(Statement::AssignElement {
+            name: target.name,
+            name_span: target.span,
+            index,
+            expression,
+            span: start.to(self.previous_span()),
+        })
+    }
 
-        // expression
-        let expr = self.parse_expression();
+    // [ EXPR ]
+    fn parse_subscript(&mut self) -> ParseResult<Rc<RefCell<ASTNode>>> {
+        let open = self.peek_span();
+        self.expect(&Token::LeftBracket)?;
+        let index = self.parse_expression()?;
+        self.expect_closing(&Token::RightBracket, open, "`[`")?;
+        Ok(index)
+    }
 
-        // ;
-        self.expect(&Token::Semicolon);
+    // ============================================================
+    // CALLSTMT → CALL ;
+    // ============================================================
+    fn parse_call_statement(&mut self) -> ParseResult<Statement> {
+        let start = self.peek_span();
+        let expression = self.parse_factor()?;
+        self.expect(&Token::Semicolon)?;
 
-        Statement::Assign { name, expression: expr }
+        Ok(Statement::Call {
+            expression,
+            span: start.to(self.previous_span()),
+        })
+    }
+
+    // ============================================================
+    // BLOCK → { STMTS }
+    // ============================================================
+    fn parse_block(&mut self) -> ParseResult<Vec<Statement>> {
+        let open = self.peek_span();
+        self.expect(&Token::LeftBrace)?;
+        let stmts = self.parse_statements();
+        self.expect_closing(&Token::RightBrace, open, "block")?;
+        Ok(stmts)
     }
 
     // ============================================================
-    // IFTHENELSE → if BOOL then { STMTS } else { STMTS }
+    // IFTHENELSE → if BOOL then { STMTS } [ else { STMTS } | else IFTHENELSE ]
+    // `else if` nests the next `if` as the only else statement.
     // ============================================================
-    fn parse_if_statement(&mut self) -> Statement {
-        self.expect(&Token::KeywordIf);
-        let cond = self.parse_boolean();
-        self.expect(&Token::KeywordThen);
+    fn parse_if_statement(&mut self) -> ParseResult<Statement> {
+        let start = self.peek_span();
+        self.expect(&Token::KeywordIf)?;
+        let cond = self.parse_boolean()?;
+        self.expect(&Token::KeywordThen)?;
 
-        self.expect(&Token::LeftBrace);
-        let then_part = self.parse_statements();
-        self.expect(&Token::RightBrace);
+        let then_part = self.parse_block()?;
 
-        self.expect(&Token::KeywordElse);
-        self.expect(&Token::LeftBrace);
-        let else_part = self.parse_statements();
-        self.expect(&Token::RightBrace);
+        let else_part = match self.peek() {
+            Token::KeywordElse => {
+                self.consume();
+                if let Token::KeywordIf = self.peek() {
+                    vec![self.parse_if_statement()?]
+                } else {
+                    self.parse_block()?
+                }
+            }
+            _ => Vec::new(),
+        };
 
-        Statement::If {
+        Ok(Statement::If {
             condition: cond,
             then_statements: then_part,
             else_statements: else_part,
-        }
+            span: start.to(self.previous_span()),
+        })
     }
 
     // ============================================================
     // WHILE → while BOOL then { STMTS }
     // ============================================================
-    fn parse_while_statement(&mut self) -> Statement {
-        self.expect(&Token::KeywordWhile);
-        let cond = self.parse_boolean();
-        self.expect(&Token::KeywordThen);
+    fn parse_while_statement(&mut self) -> ParseResult<Statement> {
+        let start = self.peek_span();
+        self.expect(&Token::KeywordWhile)?;
+        let cond = self.parse_boolean()?;
+        self.expect(&Token::KeywordThen)?;
 
-        self.expect(&Token::LeftBrace);
-        let body = self.parse_statements();
-        self.expect(&Token::RightBrace);
+        let body = self.parse_block()?;
 
-        Statement::While {
+        Ok(Statement::While {
             condition: cond,
             body,
-        }
+            span: start.to(self.previous_span()),
+        })
     }
 
     // ============================================================
-    // RET → return IDENTIFIER ;
+    // FOR → for ( [ASSIGN] ; BOOL ; [ASSIGN] ) { STMTS }
     // ============================================================
-    fn parse_return(&mut self) -> String {
-        self.expect(&Token::KeywordReturn);
+    fn parse_for_statement(&mut self) -> ParseResult<Statement> {
+        let start = self.peek_span();
+        self.expect(&Token::KeywordFor)?;
+        let open = self.peek_span();
+        self.expect(&Token::LeftParen)?;
 
-        let ident = if let Token::Identifier(name) = self.peek().clone() {
-            name
-        } else {
-            panic!("Expected identifier after 'return'");
+        let init = match self.peek() {
+            Token::Semicolon => None,
+            _ => Some(Box::new(self.parse_assignment()?)),
         };
-        self.consume();
+        self.expect(&Token::Semicolon)?;
+
+        let cond = self.parse_boolean()?;
+        self.expect(&Token::Semicolon)?;
+
+        let step = match self.peek() {
+            Token::RightParen => None,
+            _ => Some(Box::new(self.parse_assignment()?)),
+        };
+        self.expect_closing(&Token::RightParen, open, "`(`")?;
+
+        let body = self.parse_block()?;
+
+        Ok(Statement::For {
+            init,
+            condition: cond,
+            step,
+            body,
+            span: start.to(self.previous_span()),
+        })
+    }
+
+    // ============================================================
+    // DOWHILE → do { STMTS } while BOOL ;
+    // ============================================================
+    fn parse_do_while_statement(&mut self) -> ParseResult<Statement> {
+        let start = self.peek_span();
+        self.expect(&Token::KeywordDo)?;
+
+        let body = self.parse_block()?;
 
-        self.expect(&Token::Semicolon);
+        self.expect(&Token::KeywordWhile)?;
+        let cond = self.parse_boolean()?;
+        self.expect(&Token::Semicolon)?;
 
-        ident
+        Ok(Statement::DoWhile {
+            body,
+            condition: cond,
+            span: start.to(self.previous_span()),
+        })
+    }
+
+    // ============================================================
+    // RET → return VALUE ;
+    // ============================================================
+    fn parse_return(&mut self) -> ParseResult<Statement> {
+        let start = self.peek_span();
+        self.expect(&Token::KeywordReturn)?;
+
+        // expression, or a condition returned as 0/1
+        let expression = self.parse_value()?;
+
+        self.expect(&Token::Semicolon)?;
+
+        Ok(Statement::Return {
+            expression,
+            span: start.to(self.previous_span()),
+        })
     }
 
     // ============================================================
     // BOOLEAN EXPRESSIONS
+    // BOOL → BOOL || CONJ | CONJ
+    // CONJ → CONJ && NEG | NEG
+    // NEG  → ! NEG | ATOM
+    // ATOM → true | false | EXPR cmp EXPR | EXPR
+    // where the lone EXPR must be a parenthesised condition, `(a < b)`,
+    // which parse_factor reads as a VALUE. Every token is read once:
+    // nothing here backtracks.
     // ============================================================
-    fn parse_boolean(&mut self) -> BooleanExpression {
+    fn parse_boolean(&mut self) -> ParseResult<BooleanExpression> {
+        let first = self.parse_negation()?;
+        self.parse_boolean_from(first)
+    }
+
+    // The rest of a BOOL whose first NEG has already been read
+    fn parse_boolean_from(&mut self, first: BooleanExpression) -> ParseResult<BooleanExpression> {
+        let mut accumulated = self.parse_conjunction_from(first)?;
+        while let Token::LogicalOr = self.peek() {
+            self.consume();
+            let right = self.parse_conjunction()?;
+            let span = accumulated.span().to(right.span());
+            accumulated = BooleanExpression::Or(Box::new(accumulated), Box::new(right), span);
+        }
+        Ok(accumulated)
+    }
+
+    fn parse_conjunction(&mut self) -> ParseResult<BooleanExpression> {
+        let first = self.parse_negation()?;
+        self.parse_conjunction_from(first)
+    }
+
+    fn parse_conjunction_from(&mut self, first: BooleanExpression) -> ParseResult<BooleanExpression> {
+        let mut accumulated = first;
+        while let Token::LogicalAnd = self.peek() {
+            self.consume();
+            let right = self.parse_negation()?;
+            let span = accumulated.span().to(right.span());
+            accumulated = BooleanExpression::And(Box::new(accumulated), Box::new(right), span);
+        }
+        Ok(accumulated)
+    }
+
+    fn parse_negation(&mut self) -> ParseResult<BooleanExpression> {
+        if let Token::LogicalNot = self.peek() {
+            let bang = self.peek_span();
+            self.consume();
+            let operand = self.parse_negation()?;
+            let span = bang.to(operand.span());
+            return Ok(BooleanExpression::Not(Box::new(operand), span));
+        }
+        self.parse_boolean_atom()
+    }
+
+    fn parse_boolean_atom(&mut self) -> ParseResult<BooleanExpression> {
         match self.peek() {
             Token::KeywordTrue => {
+                let span = self.peek_span();
                 self.consume();
-                BooleanExpression::True
+                Ok(BooleanExpression::True(span))
             }
             Token::KeywordFalse => {
+                let span = self.peek_span();
                 self.consume();
-                BooleanExpression::False
+                Ok(BooleanExpression::False(span))
             }
             _ => {
-                // BOOL -> EXPR < EXPR | ... (needs two EXPRs)
-                let left = self.parse_expression();
+                let left = self.parse_expression()?;
+                self.parse_comparison_from(left)
+            }
+        }
+    }
 
-                match self.peek() {
-                    Token::LessThan => {
-                        self.consume();
-                        let right = self.parse_expression();
-                        BooleanExpression::CompareLessThan(left, right)
-                    }
-                    Token::LessThanEqualTo => {
-                        self.consume();
-                        let right = self.parse_expression();
-                        BooleanExpression::CompareLessThanEqualTo(left, right)
-                    }
-                    Token::GreaterThan => {
-                        self.consume();
-                        let right = self.parse_expression();
-                        BooleanExpression::CompareGreaterThan(left, right)
-                    }
-                    Token::GreaterThanEqualTo => {
-                        self.consume();
-                        let right = self.parse_expression();
-                        BooleanExpression::CompareGreaterThanEqualTo(left, right)
-                    }
-                    Token::EqualEqualTo => {
-                        self.consume();
-                        let right = self.parse_expression();
-                        BooleanExpression::CompareEqualTo(left, right)
-                    }
-                    _ => panic!("Invalid boolean operator: {:?}", self.peek()),
-                }
+    // COMPARISON → EXPR (< | <= | > | >= | == | !=) EXPR, once the left
+    // EXPR has been read. With no operator after it, a parenthesised
+    // condition such as `(a < 1 || b > 2)` is a condition on its own.
+    fn parse_comparison_from(&mut self, left: Rc<RefCell<ASTNode>>) -> ParseResult<BooleanExpression> {
+        let start = left.borrow().span();
+
+        match self.peek() {
+            Token::LessThan => {
+                self.consume();
+                let right = self.parse_expression()?;
+                Ok(BooleanExpression::CompareLessThan(left, right, start.to(self.previous_span())))
+            }
+            Token::LessThanEqualTo => {
+                self.consume();
+                let right = self.parse_expression()?;
+                Ok(BooleanExpression::CompareLessThanEqualTo(left, right, start.to(self.previous_span())))
             }
+            Token::GreaterThan => {
+                self.consume();
+                let right = self.parse_expression()?;
+                Ok(BooleanExpression::CompareGreaterThan(left, right, start.to(self.previous_span())))
+            }
+            Token::GreaterThanEqualTo => {
+                self.consume();
+                let right = self.parse_expression()?;
+                Ok(BooleanExpression::CompareGreaterThanEqualTo(left, right, start.to(self.previous_span())))
+            }
+            Token::EqualEqualTo => {
+                self.consume();
+                let right = self.parse_expression()?;
+                Ok(BooleanExpression::CompareEqualTo(left, right, start.to(self.previous_span())))
+            }
+            Token::NotEqualTo => {
+                self.consume();
+                let right = self.parse_expression()?;
+                Ok(BooleanExpression::CompareNotEqualTo(left, right, start.to(self.previous_span())))
+            }
+            _ => match &*left.borrow() {
+                ASTNode::Condition(condition, _) => Ok((**condition).clone()),
+                _ => Err(self.unexpected("comparison operator").into()),
+            },
         }
     }
 
+    // VALUE → BOOL | EXPR
+    // A full condition (`a < b && c != 0`) is read as a 0/1 value;
+    // anything that is not one is an ordinary arithmetic expression.
+    // The expression is read first and only becomes a condition when a
+    // comparison, `&&` or `||` follows it.
+    fn parse_value(&mut self) -> ParseResult<Rc<RefCell<ASTNode>>> {
+        let condition = match self.peek() {
+            // only a condition can start with these
+            Token::KeywordTrue | Token::KeywordFalse | Token::LogicalNot => self.parse_boolean()?,
+            _ => {
+                let left = self.parse_expression()?;
+                let continues_as_condition = matches!(
+                    self.peek(),
+                    Token::LessThan
+                        | Token::LessThanEqualTo
+                        | Token::GreaterThan
+                        | Token::GreaterThanEqualTo
+                        | Token::EqualEqualTo
+                        | Token::NotEqualTo
+                        | Token::LogicalAnd
+                        | Token::LogicalOr
+                );
+                if !continues_as_condition {
+                    return Ok(left);
+                }
+                let first = self.parse_comparison_from(left)?;
+                self.parse_boolean_from(first)?
+            }
+        };
+        let span = condition.span();
+        Ok(Rc::new(RefCell::new(ASTNode::Condition(Box::new(condition), span))))
+    }
+
     // ============================================================
     // ====== EXPRESSION PARSER (YOUR ORIGINAL, WITH SUB ADDED) ===
-    // EXPR → EXPR + TERM | EXPR - TERM | TERM
+    // Loosest to tightest, as in C:
+    // EXPR  → EXPR `|` XOR | XOR
+    // XOR   → XOR ^ AND | AND
+    // AND   → AND & SHIFT | SHIFT
+    // SHIFT → SHIFT (<< | >> | >>>) SUM | SUM
+    // SUM   → SUM + TERM | SUM - TERM | TERM
     // ============================================================
-    pub fn parse_expression(&mut self) -> Rc<RefCell<ASTNode>> {
-        let term_node = self.parse_term();
-        self.parse_expression_tail(term_node)
+    pub fn parse_expression(&mut self) -> ParseResult<Rc<RefCell<ASTNode>>> {
+        let mut accumulated = self.parse_bit_xor()?;
+        while let Token::BitOr = self.peek() {
+            self.consume();
+            let right = self.parse_bit_xor()?;
+            accumulated = combine(ASTNode::BitOr, accumulated, right);
+        }
+        Ok(accumulated)
+    }
+
+    fn parse_bit_xor(&mut self) -> ParseResult<Rc<RefCell<ASTNode>>> {
+        let mut accumulated = self.parse_bit_and()?;
+        while let Token::BitXor = self.peek() {
+            self.consume();
+            let right = self.parse_bit_and()?;
+            accumulated = combine(ASTNode::BitXor, accumulated, right);
+        }
+        Ok(accumulated)
+    }
+
+    fn parse_bit_and(&mut self) -> ParseResult<Rc<RefCell<ASTNode>>> {
+        let mut accumulated = self.parse_shift()?;
+        while let Token::BitAnd = self.peek() {
+            self.consume();
+            let right = self.parse_shift()?;
+            accumulated = combine(ASTNode::BitAnd, accumulated, right);
+        }
+        Ok(accumulated)
     }
 
-    fn parse_expression_tail(&mut self, accumulated: Rc<RefCell<ASTNode>>) -> Rc<RefCell<ASTNode>> {
+    fn parse_shift(&mut self) -> ParseResult<Rc<RefCell<ASTNode>>> {
+        let mut accumulated = self.parse_sum()?;
+        loop {
+            let make = match self.peek() {
+                Token::ShiftLeft => ASTNode::ShiftLeft,
+                Token::ShiftRight => ASTNode::ShiftRight,
+                Token::ShiftRightLogical => ASTNode::ShiftRightLogical,
+                _ => return Ok(accumulated),
+            };
+            self.consume();
+            let right = self.parse_sum()?;
+            accumulated = combine(make, accumulated, right);
+        }
+    }
+
+    fn parse_sum(&mut self) -> ParseResult<Rc<RefCell<ASTNode>>> {
+        let term_node = self.parse_term()?;
+        self.parse_sum_tail(term_node)
+    }
+
+    fn parse_sum_tail(&mut self, accumulated: Rc<RefCell<ASTNode>>) -> ParseResult<Rc<RefCell<ASTNode>>> {
         match self.peek() {
             Token::Plus => {
                 self.consume();
-                let next_term = self.parse_term();
-                let combined = Rc::new(RefCell::new(ASTNode::Add(accumulated, next_term)));
-                self.parse_expression_tail(combined)
+                let next_term = self.parse_term()?;
+                let span = accumulated.borrow().span().to(next_term.borrow().span());
+                let combined = Rc::new(RefCell::new(ASTNode::Add(accumulated, next_term, span)));
+                self.parse_sum_tail(combined)
             }
             Token::Minus => {
                 self.consume();
-                let next_term = self.parse_term();
-                let combined = Rc::new(RefCell::new(ASTNode::Sub(accumulated, next_term)));
-                self.parse_expression_tail(combined)
+                let next_term = self.parse_term()?;
+                let span = accumulated.borrow().span().to(next_term.borrow().span());
+                let combined = Rc::new(RefCell::new(ASTNode::Sub(accumulated, next_term, span)));
+                self.parse_sum_tail(combined)
             }
-            _ => accumulated,
+            _ => Ok(accumulated),
         }
     }
 
-    // TERM → TERM * FACTOR | FACTOR
-    fn parse_term(&mut self) -> Rc<RefCell<ASTNode>> {
-        let factor_node = self.parse_factor();
+    // TERM → TERM (* | / | %) FACTOR | FACTOR
+    fn parse_term(&mut self) -> ParseResult<Rc<RefCell<ASTNode>>> {
+        let factor_node = self.parse_factor()?;
         self.parse_term_tail(factor_node)
     }
 
-    fn parse_term_tail(&mut self, accumulated: Rc<RefCell<ASTNode>>) -> Rc<RefCell<ASTNode>> {
+    fn parse_term_tail(&mut self, accumulated: Rc<RefCell<ASTNode>>) -> ParseResult<Rc<RefCell<ASTNode>>> {
         match self.peek() {
             Token::Multiply => {
                 self.consume();
-                let next_factor = self.parse_factor();
+                let next_factor = self.parse_factor()?;
+                let span = accumulated.borrow().span().to(next_factor.borrow().span());
                 let combined =
-                    Rc::new(RefCell::new(ASTNode::Multiply(accumulated, next_factor)));
+                    Rc::new(RefCell::new(ASTNode::Multiply(accumulated, next_factor, span)));
+                self.parse_term_tail(combined)
+            }
+            Token::Divide => {
+                self.consume();
+                let next_factor = self.parse_factor()?;
+                let span = accumulated.borrow().span().to(next_factor.borrow().span());
+                let combined = Rc::new(RefCell::new(ASTNode::Divide(accumulated, next_factor, span)));
+                self.parse_term_tail(combined)
+            }
+            Token::Modulo => {
+                self.consume();
+                let next_factor = self.parse_factor()?;
+                let span = accumulated.borrow().span().to(next_factor.borrow().span());
+                let combined = Rc::new(RefCell::new(ASTNode::Modulo(accumulated, next_factor, span)));
                 self.parse_term_tail(combined)
             }
-            _ => accumulated,
+            _ => Ok(accumulated),
         }
     }
 
-    fn parse_factor(&mut self) -> Rc<RefCell<ASTNode>> {
+    // FACTOR → - FACTOR | + FACTOR | ~ FACTOR | NUMBER | IDENTIFIER | ( VALUE )
+    fn parse_factor(&mut self) -> ParseResult<Rc<RefCell<ASTNode>>> {
         match self.peek() {
+            Token::Minus => {
+                let minus = self.peek_span();
+                self.consume();
+                // `-` directly on a literal is folded into it. Any literal up to
+                // u64::MAX is kept as its bit pattern (9223372036854775808 alone
+                // is i64::MIN), but a negated one must be at least i64::MIN
+                if let Token::Number(v) = *self.peek() {
+                    let span = minus.to(self.peek_span());
+                    self.consume();
+                    return Ok(Rc::new(RefCell::new(ASTNode::Number(negate_literal(v, span)?, span))));
+                }
+                let operand = self.parse_factor()?;
+                let span = minus.to(operand.borrow().span());
+                Ok(Rc::new(RefCell::new(ASTNode::Neg(operand, span))))
+            }
+            Token::Plus => {
+                self.consume();
+                self.parse_factor()
+            }
+            Token::BitNot => {
+                let tilde = self.peek_span();
+                self.consume();
+                let operand = self.parse_factor()?;
+                let span = tilde.to(operand.borrow().span());
+                Ok(Rc::new(RefCell::new(ASTNode::BitNot(operand, span))))
+            }
             Token::Number(v) => {
-                let node = Rc::new(RefCell::new(ASTNode::Number(v.clone())));
+                let node = Rc::new(RefCell::new(ASTNode::Number(*v, self.peek_span())));
                 self.consume();
-                node
+                Ok(node)
             }
             Token::Identifier(name) => {
-                let node = Rc::new(RefCell::new(ASTNode::Identifier(name.clone())));
+                let name = name.clone();
+                let start = self.peek_span();
                 self.consume();
-                node
+                // INDEX → IDENTIFIER [ EXPR ]
+                if let Token::LeftBracket = self.peek() {
+                    let index = self.parse_subscript()?;
+                    let span = start.to(self.previous_span());
+                    return Ok(Rc::new(RefCell::new(ASTNode::Index(name, index, span))));
+                }
+                if !matches!(self.peek(), Token::LeftParen) {
+                    return Ok(Rc::new(RefCell::new(ASTNode::Identifier(name, start))));
+                }
+
+                // CALL → IDENTIFIER ( [VALUE { , VALUE }] )
+                let open = self.peek_span();
+                self.consume();
+                let mut args = Vec::new();
+                if !matches!(self.peek(), Token::RightParen) {
+                    args.push(self.parse_value()?);
+                    while let Token::Comma = self.peek() {
+                        self.consume();
+                        args.push(self.parse_value()?);
+                    }
+                }
+                self.expect_closing(&Token::RightParen, open, "`(`")?;
+
+                let span = start.to(self.previous_span());
+                Ok(Rc::new(RefCell::new(ASTNode::Call(name, args, span))))
             }
+            // ( VALUE ): a parenthesised condition is a 0/1 operand
             Token::LeftParen => {
+                let open = self.peek_span();
                 self.consume();
-                let inner = self.parse_expression();
-                if let Token::RightParen = self.peek() {
-                    self.consume();
-                } else {
-                    panic!("Expected ')'");
-                }
-                inner
+                let inner = self.parse_value()?;
+                self.expect_closing(&Token::RightParen, open, "`(`")?;
+                Ok(inner)
             }
-            other => panic!("Unexpected token in factor: {:?}", other),
+            _ => Err(self.unexpected("expression").into()),
         }
     }
 }
+
+// A binary ASTNode variant constructor, e.g. `ASTNode::BitAnd`
+type BinaryNode = fn(Rc<RefCell<ASTNode>>, Rc<RefCell<ASTNode>>, Span) -> ASTNode;
+
+// The operation a compound assignment, `++` or `--` applies to its target
+fn compound_operator(token: &Token) -> Option<BinaryNode> {
+    let make: BinaryNode = match token {
+        Token::PlusAssign | Token::Increment => ASTNode::Add,
+        Token::MinusAssign | Token::Decrement => ASTNode::Sub,
+        Token::MultiplyAssign => ASTNode::Multiply,
+        Token::DivideAssign => ASTNode::Divide,
+        Token::ModuloAssign => ASTNode::Modulo,
+        Token::BitAndAssign => ASTNode::BitAnd,
+        Token::BitOrAssign => ASTNode::BitOr,
+        Token::BitXorAssign => ASTNode::BitXor,
+        Token::ShiftLeftAssign => ASTNode::ShiftLeft,
+        Token::ShiftRightAssign => ASTNode::ShiftRight,
+        Token::ShiftRightLogicalAssign => ASTNode::ShiftRightLogical,
+        _ => return None,
+    };
+    Some(make)
+}
+
+// Binary node spanning both operands
+fn combine(make: BinaryNode, left: Rc<RefCell<ASTNode>>, right: Rc<RefCell<ASTNode>>) -> Rc<RefCell<ASTNode>> {
+    let span = left.borrow().span().to(right.borrow().span());
+    Rc::new(RefCell::new(make(left, right, span)))
+}
+
+// Bit pattern of `-value`; below i64::MIN is an error
+fn negate_literal(value: u64, span: Span) -> ParseResult<u64> {
+    if value > i64::MIN.unsigned_abs() {
+        return Err(Diagnostic::error(E_LITERAL_TOO_LARGE, format!("integer literal `-{}` is too small", value), span)
+            .with_label("does not fit in 64 bits")
+            .with_note(format!("the smallest literal is {}", i64::MIN))
+            .into());
+    }
+    Ok(value.wrapping_neg())
+}
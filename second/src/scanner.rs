@@ -1,21 +1,54 @@
+use crate::diagnostics::{
+    Diagnostic, E_INVALID_CHARACTER, E_LITERAL_TOO_LARGE, E_MALFORMED_LITERAL, E_UNTERMINATED_COMMENT,
+};
+use crate::span::Span;
+use std::fmt;
+
 #[derive(Debug, Clone, PartialEq)]
 pub enum Token {
     Identifier(String),
-    Number(String),
+    Number(u64),
 
     // arithmetic / parens
     Plus,
     Minus,        // <-- added
     Multiply,
+    Divide,
+    Modulo,
     LeftParen,
     RightParen,
 
+    // bitwise
+    BitAnd,
+    BitOr,
+    BitXor,
+    BitNot,
+    ShiftLeft,
+    ShiftRight,        // >>  arithmetic (sign-filling)
+    ShiftRightLogical, // >>> logical (zero-filling)
+
     // punctuation / separators
     Semicolon,
     Assign,
+    // compound assignment: `x OP= e` stores `x OP e`
+    PlusAssign,
+    MinusAssign,
+    MultiplyAssign,
+    DivideAssign,
+    ModuloAssign,
+    BitAndAssign,
+    BitOrAssign,
+    BitXorAssign,
+    ShiftLeftAssign,
+    ShiftRightAssign,
+    ShiftRightLogicalAssign,
+    Increment, // `x++;`
+    Decrement, // `x--;`
     Comma,
     LeftBrace,
     RightBrace,
+    LeftBracket,
+    RightBracket,
 
     // comparisons
     LessThan,
@@ -23,42 +56,226 @@ pub enum Token {
     GreaterThan,
     GreaterThanEqualTo,
     EqualEqualTo,
+    NotEqualTo,
+
+    // logical connectives
+    LogicalAnd,
+    LogicalOr,
+    LogicalNot,
 
     // keywords
     KeywordArgs, KeywordInt, KeywordIf, KeywordThen, KeywordElse,
     KeywordWhile, KeywordTrue, KeywordFalse, KeywordReturn,
+    KeywordBreak, KeywordContinue, KeywordFor, KeywordDo, KeywordFn, KeywordExtern,
+    KeywordGlobal, KeywordExport,
 
-    // end / misc
+    // end of input
     EndOfFile,
-    Invalid,
+}
+
+// How a token is named in diagnostics
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let text = match self {
+            Token::Identifier(name) => return write!(f, "identifier `{}`", name),
+            Token::Number(value) => return write!(f, "number `{}`", value),
+
+            Token::Plus => "`+`",
+            Token::Minus => "`-`",
+            Token::Multiply => "`*`",
+            Token::Divide => "`/`",
+            Token::Modulo => "`%`",
+            Token::LeftParen => "`(`",
+            Token::RightParen => "`)`",
+
+            Token::BitAnd => "`&`",
+            Token::BitOr => "`|`",
+            Token::BitXor => "`^`",
+            Token::BitNot => "`~`",
+            Token::ShiftLeft => "`<<`",
+            Token::ShiftRight => "`>>`",
+            Token::ShiftRightLogical => "`>>>`",
+
+            Token::Semicolon => "`;`",
+            Token::Assign => "`=`",
+            Token::PlusAssign => "`+=`",
+            Token::MinusAssign => "`-=`",
+            Token::MultiplyAssign => "`*=`",
+            Token::DivideAssign => "`/=`",
+            Token::ModuloAssign => "`%=`",
+            Token::BitAndAssign => "`&=`",
+            Token::BitOrAssign => "`|=`",
+            Token::BitXorAssign => "`^=`",
+            Token::ShiftLeftAssign => "`<<=`",
+            Token::ShiftRightAssign => "`>>=`",
+            Token::ShiftRightLogicalAssign => "`>>>=`",
+            Token::Increment => "`++`",
+            Token::Decrement => "`--`",
+            Token::Comma => "`,`",
+            Token::LeftBrace => "`{`",
+            Token::RightBrace => "`}`",
+            Token::LeftBracket => "`[`",
+            Token::RightBracket => "`]`",
+
+            Token::LessThan => "`<`",
+            Token::LessThanEqualTo => "`<=`",
+            Token::GreaterThan => "`>`",
+            Token::GreaterThanEqualTo => "`>=`",
+            Token::EqualEqualTo => "`==`",
+            Token::NotEqualTo => "`!=`",
+
+            Token::LogicalAnd => "`&&`",
+            Token::LogicalOr => "`||`",
+            Token::LogicalNot => "`!`",
+
+            Token::KeywordArgs => "`args`",
+            Token::KeywordInt => "`int`",
+            Token::KeywordIf => "`if`",
+            Token::KeywordThen => "`then`",
+            Token::KeywordElse => "`else`",
+            Token::KeywordWhile => "`while`",
+            Token::KeywordTrue => "`true`",
+            Token::KeywordFalse => "`false`",
+            Token::KeywordReturn => "`return`",
+            Token::KeywordBreak => "`break`",
+            Token::KeywordContinue => "`continue`",
+            Token::KeywordFor => "`for`",
+            Token::KeywordDo => "`do`",
+            Token::KeywordFn => "`fn`",
+            Token::KeywordExtern => "`extern`",
+            Token::KeywordGlobal => "`global`",
+            Token::KeywordExport => "`export`",
+
+            Token::EndOfFile => "end of file",
+        };
+        f.write_str(text)
+    }
+}
+
+// A token together with the source region it was read from
+#[derive(Debug, Clone, PartialEq)]
+pub struct SpannedToken {
+    pub token: Token,
+    pub span: Span,
 }
 
 pub struct Scanner {
     position: usize,
     input_chars: Vec<char>,
+
+    // location of `position`: byte offset, 1-based line and column
+    offset: usize,
+    line: usize,
+    column: usize,
+
+    diagnostics: Vec<Diagnostic>,
 }
 
 impl Scanner {
     pub fn new(source: String) -> Self {
-        Scanner { position: 0, input_chars: source.chars().collect() }
+        Scanner {
+            position: 0,
+            input_chars: source.chars().collect(),
+            offset: 0,
+            line: 1,
+            column: 1,
+            diagnostics: Vec::new(),
+        }
     }
 
     fn current_char(&self) -> Option<char> {
         self.input_chars.get(self.position).copied()
     }
 
+    fn char_after_current(&self) -> Option<char> {
+        self.input_chars.get(self.position + 1).copied()
+    }
+
     fn advance(&mut self) -> Option<char> {
         let ch = self.current_char();
-        if ch.is_some() { self.position += 1; }
+        if let Some(c) = ch {
+            self.position += 1;
+            self.offset += c.len_utf8();
+            if c == '\n' {
+                self.line += 1;
+                self.column = 1;
+            } else {
+                self.column += 1;
+            }
+        }
         ch
     }
 
-    fn skip_whitespace(&mut self) {
-        while let Some(ch) = self.current_char() {
-            if ch.is_whitespace() { self.advance(); } else { break; }
+    // Zero-width span at the current reading position
+    fn here(&self) -> Span {
+        Span::new(self.offset, self.offset, self.line, self.column)
+    }
+
+    // Span from `start` up to the current reading position
+    fn span_from(&self, start: Span) -> Span {
+        Span::new(start.start, self.offset, start.line, start.column)
+    }
+
+    // Whitespace, `// line` comments and (nestable) `/* block */` comments
+    fn skip_whitespace_and_comments(&mut self) {
+        loop {
+            match (self.current_char(), self.char_after_current()) {
+                (Some(ch), _) if ch.is_whitespace() => {
+                    self.advance();
+                }
+                (Some('/'), Some('/')) => {
+                    while let Some(ch) = self.current_char() {
+                        if ch == '\n' { break; }
+                        self.advance();
+                    }
+                }
+                (Some('/'), Some('*')) => self.skip_block_comment(),
+                _ => break,
+            }
         }
     }
 
+    // Called at `/*`; consumes through the matching `*/`, counting nested pairs
+    fn skip_block_comment(&mut self) {
+        let mut open: Vec<Span> = Vec::new();
+
+        loop {
+            match (self.current_char(), self.char_after_current()) {
+                (Some('/'), Some('*')) => {
+                    let start = self.here();
+                    self.advance();
+                    self.advance();
+                    open.push(self.span_from(start));
+                }
+                (Some('*'), Some('/')) => {
+                    self.advance();
+                    self.advance();
+                    open.pop();
+                    if open.is_empty() {
+                        return;
+                    }
+                }
+                (Some(_), _) => {
+                    self.advance();
+                }
+                (None, _) => break,
+            }
+        }
+
+        // Hit end of file with comments still open: report the outermost one
+        let closers = "*/".repeat(open.len());
+        let mut d = Diagnostic::error(E_UNTERMINATED_COMMENT, "unterminated block comment", open[0])
+            .with_label("comment starts here")
+            .with_suggestion(format!("insert `{}` at the end of the file", closers), self.here(), closers);
+        for inner in &open[1..] {
+            d = d.with_secondary(*inner, "nested comment, also unterminated");
+        }
+        if open.len() > 1 {
+            d = d.with_note("block comments nest: every `/*` needs its own `*/`");
+        }
+        self.diagnostics.push(d);
+    }
+
     fn collect_while<F: Fn(char) -> bool>(&mut self, first: char, cond: F) -> String {
         let mut text = String::from(first);
         while let Some(next) = self.current_char() {
@@ -79,36 +296,77 @@ impl Scanner {
             "true"   => Token::KeywordTrue,
             "false"  => Token::KeywordFalse,
             "return" => Token::KeywordReturn,
+            "break"  => Token::KeywordBreak,
+            "continue" => Token::KeywordContinue,
+            "for"    => Token::KeywordFor,
+            "do"     => Token::KeywordDo,
+            "fn"     => Token::KeywordFn,
+            "extern" => Token::KeywordExtern,
+            "global" => Token::KeywordGlobal,
+            "export" => Token::KeywordExport,
             _        => Token::Identifier(s),
         }
     }
 
-    fn next_token(&mut self) -> Option<Token> {
-        self.skip_whitespace();
+    // `compound` if an `=` follows (consuming it), otherwise `plain`
+    fn or_assign(&mut self, plain: Token, compound: Token) -> Token {
+        if matches!(self.current_char(), Some('=')) {
+            self.advance();
+            compound
+        } else {
+            plain
+        }
+    }
+
+    // Next real token; characters that start no token are reported and skipped
+    fn next_token(&mut self) -> Option<SpannedToken> {
+        loop {
+            self.skip_whitespace_and_comments();
 
-        let ch = self.advance()?; // EOF → None
+            let start = self.here();
+            let ch = self.advance()?; // EOF → None
 
+            if let Some(tok) = self.scan_token(ch, start) {
+                return Some(SpannedToken { token: tok, span: self.span_from(start) });
+            }
+        }
+    }
+
+    // The token starting with `ch` (already consumed), or None after
+    // reporting `ch` as an invalid character
+    fn scan_token(&mut self, ch: char, start: Span) -> Option<Token> {
         // Multi-char operators first (using lookahead at current_char())
         let tok = match ch {
-            // <= or <
-            '<' => {
-                if matches!(self.current_char(), Some('=')) {
+            // <=, <<, <<= or <
+            '<' => match self.current_char() {
+                Some('=') => {
                     self.advance();
                     Token::LessThanEqualTo
-                } else {
-                    Token::LessThan
                 }
-            }
+                Some('<') => {
+                    self.advance();
+                    self.or_assign(Token::ShiftLeft, Token::ShiftLeftAssign)
+                }
+                _ => Token::LessThan,
+            },
 
-            // >= or >
-            '>' => {
-                if matches!(self.current_char(), Some('=')) {
+            // >=, >>, >>=, >>>, >>>= or >
+            '>' => match (self.current_char(), self.char_after_current()) {
+                (Some('='), _) => {
                     self.advance();
                     Token::GreaterThanEqualTo
-                } else {
-                    Token::GreaterThan
                 }
-            }
+                (Some('>'), Some('>')) => {
+                    self.advance();
+                    self.advance();
+                    self.or_assign(Token::ShiftRightLogical, Token::ShiftRightLogicalAssign)
+                }
+                (Some('>'), _) => {
+                    self.advance();
+                    self.or_assign(Token::ShiftRight, Token::ShiftRightAssign)
+                }
+                _ => Token::GreaterThan,
+            },
 
             // == or =
             '=' => {
@@ -120,23 +378,71 @@ impl Scanner {
                 }
             }
 
+            // && or &, || or |
+            '&' => {
+                if matches!(self.current_char(), Some('&')) {
+                    self.advance();
+                    Token::LogicalAnd
+                } else {
+                    self.or_assign(Token::BitAnd, Token::BitAndAssign)
+                }
+            }
+            '|' => {
+                if matches!(self.current_char(), Some('|')) {
+                    self.advance();
+                    Token::LogicalOr
+                } else {
+                    self.or_assign(Token::BitOr, Token::BitOrAssign)
+                }
+            }
+            '^' => self.or_assign(Token::BitXor, Token::BitXorAssign),
+            '~' => Token::BitNot,
+            // != or !
+            '!' => {
+                if matches!(self.current_char(), Some('=')) {
+                    self.advance();
+                    Token::NotEqualTo
+                } else {
+                    Token::LogicalNot
+                }
+            }
+
             // single-char punctuation
             ';' => Token::Semicolon,
             ',' => Token::Comma,
             '{' => Token::LeftBrace,
             '}' => Token::RightBrace,
+            '[' => Token::LeftBracket,
+            ']' => Token::RightBracket,
             '(' => Token::LeftParen,
             ')' => Token::RightParen,
 
-            // arithmetic
-            '+' => Token::Plus,
-            '-' => Token::Minus,        // <-- added
-            '*' => Token::Multiply,
+            // arithmetic; `++` / `--` are always one token, as in C,
+            // so negating twice needs a space or parentheses: `- -x`
+            '+' if matches!(self.current_char(), Some('+')) => {
+                self.advance();
+                Token::Increment
+            }
+            '-' if matches!(self.current_char(), Some('-')) => {
+                self.advance();
+                Token::Decrement
+            }
+            '+' => self.or_assign(Token::Plus, Token::PlusAssign),
+            '-' => self.or_assign(Token::Minus, Token::MinusAssign),
+            '*' => self.or_assign(Token::Multiply, Token::MultiplyAssign),
+            '/' => self.or_assign(Token::Divide, Token::DivideAssign), // `//` and `/*` were already skipped as comments
+            '%' => self.or_assign(Token::Modulo, Token::ModuloAssign),
 
-            // number literal (unsigned)
+            // number literal (unsigned): 42, 1_000, 0xff, 0b1010, 0o17
             d if d.is_ascii_digit() => {
-                let digits = self.collect_while(d, |c| c.is_ascii_digit());
-                Token::Number(digits)
+                let text = self.collect_while(d, |c| c.is_ascii_alphanumeric() || c == '_');
+                match parse_integer_literal(&text) {
+                    Ok(value) => Token::Number(value),
+                    Err(problem) => {
+                        self.diagnostics.push(problem.into_diagnostic(&text, self.span_from(start)));
+                        Token::Number(0)
+                    }
+                }
             }
 
             // identifier / keyword: [A-Za-z_][A-Za-z0-9_]*
@@ -146,23 +452,118 @@ impl Scanner {
             }
 
             // unknown char
-            _ => Token::Invalid,
+            other => {
+                self.diagnostics.push(
+                    Diagnostic::error(
+                        E_INVALID_CHARACTER,
+                        format!("invalid character `{}`", other.escape_debug()),
+                        self.span_from(start),
+                    )
+                    .with_label("not part of any token"),
+                );
+                return None;
+            }
         };
 
         Some(tok)
     }
 
-    pub fn tokenize(&mut self) -> Vec<Token> {
+    pub fn tokenize(&mut self) -> (Vec<SpannedToken>, Vec<Diagnostic>) {
         let mut tokens = Vec::new();
         while let Some(tok) = self.next_token() {
             tokens.push(tok);
         }
-        tokens.push(Token::EndOfFile);
-        tokens
+        tokens.push(SpannedToken { token: Token::EndOfFile, span: self.here() });
+
+        (tokens, std::mem::take(&mut self.diagnostics))
+    }
+}
+
+// ===============================================================
+// Integer literals
+// An optional radix prefix (0x, 0b, 0o) followed by digits of that radix;
+// a single `_` may separate two digits, but cannot follow the prefix,
+// another `_` or end the literal. The value must
+// fit in 64 bits; whether it is read as signed is up to the operations
+// applied to it.
+// ===============================================================
+enum LiteralProblem {
+    NoDigits(&'static str),
+    InvalidDigit(char, &'static str),
+    TooLarge(&'static str),
+    MisplacedUnderscore(&'static str, &'static str),
+}
+
+impl LiteralProblem {
+    fn into_diagnostic(self, text: &str, span: Span) -> Diagnostic {
+        match self {
+            LiteralProblem::NoDigits(kind) => {
+                Diagnostic::error(E_MALFORMED_LITERAL, format!("{} literal `{}` has no digits", kind, text), span)
+                    .with_label("expected at least one digit after the prefix")
+            }
+            LiteralProblem::InvalidDigit(c, kind) => {
+                Diagnostic::error(E_MALFORMED_LITERAL, format!("invalid digit `{}` in {} literal", c, kind), span)
+                    .with_label(format!("`{}` is not a {} digit", c, kind))
+            }
+            LiteralProblem::MisplacedUnderscore(kind, reason) => {
+                Diagnostic::error(E_MALFORMED_LITERAL, format!("misplaced `_` in {} literal `{}`", kind, text), span)
+                    .with_label(reason)
+                    .with_note("`_` may only separate two digits")
+            }
+            LiteralProblem::TooLarge(kind) => {
+                Diagnostic::error(E_LITERAL_TOO_LARGE, format!("{} literal `{}` is too large", kind, text), span)
+                    .with_label("does not fit in 64 bits")
+                    .with_note(format!("the largest literal is {} (0x{:x})", u64::MAX, u64::MAX))
+            }
+        }
+    }
+}
+
+fn parse_integer_literal(text: &str) -> Result<u64, LiteralProblem> {
+    let lower = text.get(..2).map(|p| p.to_ascii_lowercase());
+    let (radix, kind, digits) = match lower.as_deref() {
+        Some("0x") => (16, "hexadecimal", &text[2..]),
+        Some("0b") => (2, "binary", &text[2..]),
+        Some("0o") => (8, "octal", &text[2..]),
+        _ => (10, "decimal", text),
+    };
+
+    let mut value: u64 = 0;
+    let mut seen_digit = false;
+    let mut after_underscore = false;
+    for c in digits.chars() {
+        if c == '_' {
+            if !seen_digit {
+                return Err(LiteralProblem::MisplacedUnderscore(kind, "`_` cannot follow the prefix"));
+            }
+            if after_underscore {
+                return Err(LiteralProblem::MisplacedUnderscore(kind, "`_` cannot follow another `_`"));
+            }
+            after_underscore = true;
+            continue;
+        }
+        after_underscore = false;
+        let digit = c.to_digit(radix).ok_or(LiteralProblem::InvalidDigit(c, kind))?;
+        value = value
+            .checked_mul(radix as u64)
+            .and_then(|v| v.checked_add(digit as u64))
+            .ok_or(LiteralProblem::TooLarge(kind))?;
+        seen_digit = true;
+    }
+
+    if !seen_digit {
+        return Err(LiteralProblem::NoDigits(kind));
+    }
+    if after_underscore {
+        return Err(LiteralProblem::MisplacedUnderscore(kind, "`_` cannot end a literal"));
     }
+    Ok(value)
 }
 
-pub fn scan_source(source: &str) -> Vec<Token> {
+/// Every token of `source` plus every lexical error in it. Invalid
+/// characters are skipped and malformed literals read as 0, so the
+/// tokens can still be parsed for further errors.
+pub fn scan_source(source: &str) -> (Vec<SpannedToken>, Vec<Diagnostic>) {
     let mut lexer = Scanner::new(source.to_string());
     lexer.tokenize()
 }
@@ -0,0 +1,482 @@
+use crate::ast::{ASTNode, BooleanExpression, Declaration, Function, Program, Statement};
+use crate::codegen::RUNTIME_FUNCTIONS;
+use crate::diagnostics::{
+    Diagnostic, E_ARITY_MISMATCH, E_ARRAY_LENGTH, E_ARRAY_WITHOUT_INDEX, E_DUPLICATE_DECLARATION,
+    E_DUPLICATE_FUNCTION, E_JUMP_OUTSIDE_LOOP, E_NOT_AN_ARRAY, E_RESERVED_NAME, E_SHADOWED_ARGUMENT,
+    E_UNDECLARED_VARIABLE, E_UNDEFINED_FUNCTION,
+};
+use crate::span::Span;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+
+// ===============================================================
+// Name resolution
+// Every identifier must name exactly one `args` or `int` declaration
+// of its function or a `global` of the file (locals and arguments hide
+// globals of the same name), and every call must name a function defined in the
+// file and pass it the right number of arguments. Runs between parsing
+// and codegen, so codegen only ever sees programs whose names all
+// resolve. Arrays are only ever used with an index, and nothing else
+// is. The same walk also checks that `break` and `continue` only
+// appear inside a loop.
+// ===============================================================
+
+// Longest array a function may declare; its elements live in the frame
+const MAX_ARRAY_LENGTH: u64 = 1 << 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SymbolKind {
+    Argument,
+    Local,
+}
+
+#[derive(Debug, Clone)]
+struct Symbol {
+    kind: SymbolKind,
+    span: Span,
+    // `Some(n)` for an array of n elements
+    length: Option<u64>,
+}
+
+// What a call needs to know about the function it names
+struct Signature {
+    arity: usize,
+    span: Span,
+}
+
+// The names visible inside one function
+struct SymbolTable<'a> {
+    symbols: HashMap<String, Symbol>,
+    globals: &'a HashMap<String, Span>,
+    functions: &'a HashMap<String, Signature>,
+    // number of loop bodies around the statement being checked
+    loop_depth: usize,
+    diagnostics: Vec<Diagnostic>,
+}
+
+impl<'a> SymbolTable<'a> {
+    fn new(globals: &'a HashMap<String, Span>, functions: &'a HashMap<String, Signature>) -> Self {
+        SymbolTable {
+            symbols: HashMap::new(),
+            globals,
+            functions,
+            loop_depth: 0,
+            diagnostics: Vec::new(),
+        }
+    }
+
+    fn declare(&mut self, decl: &Declaration, kind: SymbolKind) {
+        if let Some(previous) = self.symbols.get(&decl.name) {
+            let d = match (previous.kind, kind) {
+                (SymbolKind::Argument, SymbolKind::Local) => Diagnostic::error(
+                    E_SHADOWED_ARGUMENT,
+                    format!("local variable `{}` shadows an argument", decl.name),
+                    decl.span,
+                )
+                .with_label("declared again with `int`")
+                .with_secondary(previous.span, "argument declared here")
+                .with_note("arguments already have a stack slot; assign to the argument directly or pick another name"),
+
+                _ => Diagnostic::error(
+                    E_DUPLICATE_DECLARATION,
+                    format!("`{}` is declared more than once", decl.name),
+                    decl.span,
+                )
+                .with_label("declared again here")
+                .with_secondary(previous.span, "first declared here"),
+            };
+            self.diagnostics.push(d);
+            return;
+        }
+
+        if let Some(length) = decl.length.filter(|n| !(1..=MAX_ARRAY_LENGTH).contains(n)) {
+            self.diagnostics.push(
+                Diagnostic::error(E_ARRAY_LENGTH, format!("array `{}` has length {}", decl.name, length), decl.span)
+                    .with_label("declared here")
+                    .with_note(format!("an array holds between 1 and {} elements", MAX_ARRAY_LENGTH)),
+            );
+        }
+
+        self.symbols.insert(
+            decl.name.clone(),
+            Symbol {
+                kind,
+                span: decl.span,
+                length: decl.length,
+            },
+        );
+    }
+
+    // `name` used on its own (`indexed` false) or as `name[...]`
+    fn resolve(&mut self, name: &str, span: Span, indexed: bool) {
+        // the name is where an indexed use starts
+        let name_span = Span::new(span.start, span.start + name.len(), span.line, span.column);
+
+        if let Some(symbol) = self.symbols.get(name) {
+            let d = match (symbol.length, indexed) {
+                (Some(_), false) => Diagnostic::error(
+                    E_ARRAY_WITHOUT_INDEX,
+                    format!("array `{}` used without an index", name),
+                    span,
+                )
+                .with_label(format!("use one element, e.g. `{}[0]`", name))
+                .with_secondary(symbol.span, "declared as an array here")
+                .with_note("arrays cannot be assigned, passed or returned as a whole"),
+
+                (None, true) => Diagnostic::error(E_NOT_AN_ARRAY, format!("`{}` is not an array", name), name_span)
+                    .with_label("indexed here")
+                    .with_secondary(
+                        symbol.span,
+                        match symbol.kind {
+                            SymbolKind::Argument => "declared as an argument here",
+                            SymbolKind::Local => "declared without a length here",
+                        },
+                    ),
+
+                _ => return,
+            };
+            self.diagnostics.push(d);
+            return;
+        }
+        if let Some(declared) = self.globals.get(name) {
+            if indexed {
+                self.diagnostics.push(
+                    Diagnostic::error(E_NOT_AN_ARRAY, format!("`{}` is not an array", name), name_span)
+                        .with_label("indexed here")
+                        .with_secondary(*declared, "declared as a `global int` here")
+                        .with_note("only `int` locals can be arrays"),
+                );
+            }
+            return;
+        }
+
+        let mut d = Diagnostic::error(E_UNDECLARED_VARIABLE, format!("undeclared variable `{}`", name), name_span)
+            .with_label("not declared in `args`, `int` or `global`");
+        if let Some(similar) = most_similar(name, self.symbols.keys().chain(self.globals.keys())) {
+            d = d.with_suggestion(format!("a variable with a similar name exists: `{}`", similar), name_span, similar);
+        }
+        self.diagnostics.push(d);
+    }
+
+    fn call(&mut self, name: &str, arg_count: usize, span: Span) {
+        // the name is where the call starts
+        let name_span = Span::new(span.start, span.start + name.len(), span.line, span.column);
+
+        let d = match self.functions.get(name) {
+            Some(callee) if callee.arity == arg_count => return,
+
+            Some(callee) => Diagnostic::error(
+                E_ARITY_MISMATCH,
+                format!(
+                    "`{}` takes {} but {} supplied",
+                    name,
+                    count(callee.arity, "argument"),
+                    if arg_count == 1 { "1 was".to_string() } else { format!("{} were", arg_count) }
+                ),
+                span,
+            )
+            .with_label(format!("called with {}", count(arg_count, "argument")))
+            .with_secondary(callee.span, format!("`{}` declared here with {}", name, count(callee.arity, "argument"))),
+
+            None => {
+                let mut d = Diagnostic::error(E_UNDEFINED_FUNCTION, format!("undefined function `{}`", name), name_span)
+                    .with_label("no `fn` or `extern fn` with this name in the file");
+                if let Some(similar) = most_similar(name, self.functions.keys()) {
+                    d = d.with_suggestion(format!("a function with a similar name exists: `{}`", similar), name_span, similar);
+                }
+                d
+            }
+        };
+        self.diagnostics.push(d);
+    }
+}
+
+// Closest of `candidates` within a couple of edits of `name`, for "did you mean"
+fn most_similar<'a>(name: &str, candidates: impl Iterator<Item = &'a String>) -> Option<String> {
+    let limit = match name.chars().count() {
+        0..=2 => return None, // every short name is "close" to every other
+        3..=5 => 1,
+        _ => 2,
+    };
+    candidates
+        .map(|candidate| (edit_distance(name, candidate), candidate))
+        .filter(|(distance, _)| *distance <= limit)
+        .min()
+        .map(|(_, candidate)| candidate.clone())
+}
+
+// "1 argument", "2 arguments"
+fn count(n: usize, noun: &str) -> String {
+    if n == 1 {
+        format!("1 {}", noun)
+    } else {
+        format!("{} {}s", n, noun)
+    }
+}
+
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+
+    for (i, ca) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, cb) in b.iter().enumerate() {
+            let substitution = diagonal + usize::from(ca != *cb);
+            diagonal = row[j + 1];
+            row[j + 1] = substitution.min(row[j] + 1).min(row[j + 1] + 1);
+        }
+    }
+    row[b.len()]
+}
+
+pub fn check_program(p: &Program) -> Result<(), Vec<Diagnostic>> {
+    let mut diagnostics = Vec::new();
+
+    // `extern` declarations and definitions share one namespace
+    let signatures = p
+        .externs
+        .iter()
+        .map(|e| (&e.name, e.name_span, e.arg_declarations.len()))
+        .chain(p.functions.iter().map(|f| (&f.name, f.name_span, f.arg_declarations.len())));
+
+    let mut functions: HashMap<String, Signature> = HashMap::new();
+    for (name, span, arity) in signatures {
+        if let Some(previous) = functions.get(name) {
+            diagnostics.push(
+                Diagnostic::error(E_DUPLICATE_FUNCTION, format!("function `{}` is defined more than once", name), span)
+                    .with_label("defined again here")
+                    .with_secondary(previous.span, "first defined here"),
+            );
+            continue;
+        }
+        functions.insert(name.clone(), Signature { arity, span });
+    }
+
+    // globals share the assembler's symbol namespace with functions
+    let mut globals: HashMap<String, Span> = HashMap::new();
+    for g in &p.globals {
+        if let Some(previous) = globals.get(&g.name) {
+            diagnostics.push(
+                Diagnostic::error(E_DUPLICATE_DECLARATION, format!("`{}` is declared more than once", g.name), g.span)
+                    .with_label("declared again here")
+                    .with_secondary(*previous, "first declared here"),
+            );
+            continue;
+        }
+        if let Some(function) = functions.get(&g.name) {
+            diagnostics.push(
+                Diagnostic::error(
+                    E_DUPLICATE_FUNCTION,
+                    format!("global `{}` has the same name as a function", g.name),
+                    g.span,
+                )
+                .with_label("global declared here")
+                .with_secondary(function.span, "function declared here")
+                .with_note("both become assembler symbols; rename one of them"),
+            );
+        }
+        globals.insert(g.name.clone(), g.span);
+    }
+
+    // A definition would take the place of the C function the traps call
+    let defined = p
+        .functions
+        .iter()
+        .map(|f| (&f.name, f.name_span, "function"))
+        .chain(p.globals.iter().map(|g| (&g.name, g.span, "global")));
+    for (name, span, what) in defined.filter(|(name, _, _)| RUNTIME_FUNCTIONS.contains(&name.as_str())) {
+        diagnostics.push(
+            Diagnostic::error(E_RESERVED_NAME, format!("`{}` cannot be used as a {} name", name, what), span)
+                .with_label("reserved for the runtime")
+                .with_note(format!(
+                    "the division-by-zero and bounds-check traps call the C function `{}`; pick another name",
+                    name
+                )),
+        );
+    }
+
+    for f in &p.functions {
+        diagnostics.extend(check_function(f, &globals, &functions));
+    }
+
+    if !diagnostics.is_empty() {
+        return Err(diagnostics);
+    }
+    Ok(())
+}
+
+fn check_function(
+    f: &Function,
+    globals: &HashMap<String, Span>,
+    functions: &HashMap<String, Signature>,
+) -> Vec<Diagnostic> {
+    let mut table = SymbolTable::new(globals, functions);
+
+    for a in &f.arg_declarations {
+        table.declare(a, SymbolKind::Argument);
+    }
+    for v in &f.var_declarations {
+        table.declare(v, SymbolKind::Local);
+    }
+
+    for s in &f.statements {
+        check_stmt(s, &mut table);
+    }
+
+    table.diagnostics
+}
+
+fn check_stmt(s: &Statement, table: &mut SymbolTable) {
+    match s {
+        Statement::Assign {
+            name,
+            name_span,
+            expression,
+            compound,
+            ..
+        } => {
+            check_expr(expression, table);
+            // the expression of `x += e` has resolved `x` already
+            if !compound {
+                table.resolve(name, *name_span, false);
+            }
+        }
+
+        Statement::AssignElement {
+            name,
+            name_span,
+            index,
+            expression,
+            ..
+        } => {
+            check_expr(index, table);
+            check_expr(expression, table);
+            table.resolve(name, *name_span, true);
+        }
+
+        Statement::If {
+            condition,
+            then_statements,
+            else_statements,
+            ..
+        } => {
+            check_bool(condition, table);
+            for s in then_statements {
+                check_stmt(s, table);
+            }
+            for s in else_statements {
+                check_stmt(s, table);
+            }
+        }
+
+        Statement::While { condition, body, .. } => {
+            check_bool(condition, table);
+            table.loop_depth += 1;
+            for s in body {
+                check_stmt(s, table);
+            }
+            table.loop_depth -= 1;
+        }
+
+        Statement::For {
+            init,
+            condition,
+            step,
+            body,
+            ..
+        } => {
+            if let Some(init) = init {
+                check_stmt(init, table);
+            }
+            check_bool(condition, table);
+            if let Some(step) = step {
+                check_stmt(step, table);
+            }
+            table.loop_depth += 1;
+            for s in body {
+                check_stmt(s, table);
+            }
+            table.loop_depth -= 1;
+        }
+
+        Statement::DoWhile { body, condition, .. } => {
+            table.loop_depth += 1;
+            for s in body {
+                check_stmt(s, table);
+            }
+            table.loop_depth -= 1;
+            check_bool(condition, table);
+        }
+
+        Statement::Return { expression, .. } | Statement::Call { expression, .. } => check_expr(expression, table),
+
+        Statement::Break { span } | Statement::Continue { span } => {
+            if table.loop_depth == 0 {
+                let keyword = if matches!(s, Statement::Break { .. }) { "break" } else { "continue" };
+                table.diagnostics.push(
+                    Diagnostic::error(E_JUMP_OUTSIDE_LOOP, format!("`{}` outside of a loop", keyword), *span)
+                        .with_label(format!("cannot `{}` outside of a `while`, `for` or `do` loop", keyword)),
+                );
+            }
+        }
+    }
+}
+
+fn check_bool(b: &BooleanExpression, table: &mut SymbolTable) {
+    use BooleanExpression::*;
+
+    match b {
+        True(_) | False(_) => {}
+        CompareLessThan(l, r, _)
+        | CompareLessThanEqualTo(l, r, _)
+        | CompareGreaterThan(l, r, _)
+        | CompareGreaterThanEqualTo(l, r, _)
+        | CompareEqualTo(l, r, _)
+        | CompareNotEqualTo(l, r, _) => {
+            check_expr(l, table);
+            check_expr(r, table);
+        }
+        And(l, r, _) | Or(l, r, _) => {
+            check_bool(l, table);
+            check_bool(r, table);
+        }
+        Not(inner, _) => check_bool(inner, table),
+    }
+}
+
+fn check_expr(node: &Rc<RefCell<ASTNode>>, table: &mut SymbolTable) {
+    use ASTNode::*;
+
+    match &*node.borrow() {
+        Number(_, _) => {}
+        Identifier(name, span) => table.resolve(name, *span, false),
+        Index(name, index, span) => {
+            check_expr(index, table);
+            table.resolve(name, *span, true);
+        }
+        Neg(operand, _) | BitNot(operand, _) => check_expr(operand, table),
+        Condition(b, _) => check_bool(b, table),
+        Call(name, args, span) => {
+            for arg in args {
+                check_expr(arg, table);
+            }
+            table.call(name, args.len(), *span);
+        }
+        Add(l, r, _)
+        | Sub(l, r, _)
+        | Multiply(l, r, _)
+        | Divide(l, r, _)
+        | Modulo(l, r, _)
+        | BitAnd(l, r, _)
+        | BitOr(l, r, _)
+        | BitXor(l, r, _)
+        | ShiftLeft(l, r, _)
+        | ShiftRight(l, r, _)
+        | ShiftRightLogical(l, r, _) => {
+            check_expr(l, table);
+            check_expr(r, table);
+        }
+    }
+}
@@ -0,0 +1,13 @@
+#include <stdio.h>
+#include <stdlib.h>
+
+// Hex / binary / octal literals with `_` separators; 0x1_0000_0000 * 3
+// does not fit in 32 bits, so the compiler must load it with movabsq.
+extern unsigned long foo(unsigned long a) __asm("foo");
+
+int main() {
+
+    printf("%lu\n", foo(1));   // expect 12884909889
+    printf("%lu\n", foo(0));   // expect 12884909888
+    return 0;
+}
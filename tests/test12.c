@@ -0,0 +1,13 @@
+#include <stdio.h>
+#include <stdlib.h>
+
+// Prefix minus on identifiers, parenthesised expressions and literals,
+// including the most negative 64-bit literal.
+extern long foo(long a, long b, long c) __asm("foo");
+
+int main() {
+
+    printf("%ld\n", foo(5, 1, 0));   // expect -14
+    printf("%ld\n", foo(0, 0, 1));   // expect -9223372036854775806
+    return 0;
+}
@@ -0,0 +1,20 @@
+#include <stdio.h>
+#include <stdint.h>
+
+// Part 2: two arguments `a`, `b`; returns (a / b) * 100 + a % b
+// __asm("foo") is for macOS so the symbol name matches the assembly label `foo`
+extern long foo(long a, long b) __asm("foo");
+
+int main(void) {
+
+    // a = 17, b = 5  -> expect 302
+    printf("%ld\n", foo(17, 5));
+
+    // a = -17, b = 5  -> expect -302 (quotient -3, remainder -2)
+    printf("%ld\n", foo(-17, 5));
+
+    // a = -17, b = -5  -> expect 298 (quotient 3, remainder -2)
+    printf("%ld\n", foo(-17, -5));
+
+    return 0;
+}
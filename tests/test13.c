@@ -0,0 +1,13 @@
+#include <stdio.h>
+#include <stdlib.h>
+
+// Ten identifiers: the first six arrive in registers, g..j on the stack.
+extern long foo(long a, long b, long c, long d, long e,
+                long f, long g, long h, long i, long j) __asm("foo");
+
+int main() {
+
+    printf("%ld\n", foo(1, 1, 1, 1, 1, 1, 1, 1, 1, 1));      // expect 55
+    printf("%ld\n", foo(0, 0, 0, 0, 0, 0, 1, 10, 100, 1000)); // expect 10987
+    return 0;
+}
@@ -0,0 +1,20 @@
+#include <stdio.h>
+#include <stdint.h>
+
+// Part 2: prefix `-` and `+`; returns -a * 2 - -b + 3, or -1 when a is INT64_MIN
+// __asm("foo") is for macOS so the symbol name matches the assembly label `foo`
+extern long foo(long a, long b) __asm("foo");
+
+int main(void) {
+
+    // a = 5, b = 2  -> expect -5
+    printf("%ld\n", foo(5, 2));
+
+    // a = -4, b = -1  -> expect 10
+    printf("%ld\n", foo(-4, -1));
+
+    // a = INT64_MIN, b = 0  -> expect -1
+    printf("%ld\n", foo(INT64_MIN, 0));
+
+    return 0;
+}
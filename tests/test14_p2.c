@@ -0,0 +1,23 @@
+#include <stdio.h>
+#include <stdint.h>
+
+// Part 2: `&&`, `||` and `!` with short-circuit evaluation
+// __asm("foo") is for macOS so the symbol name matches the assembly label `foo`
+extern long foo(long a, long b) __asm("foo");
+
+int main(void) {
+
+    // a = 10, b = 0  -> expect 11 (a / b is skipped)
+    printf("%ld\n", foo(10, 0));
+
+    // a = 10, b = 5  -> expect 12
+    printf("%ld\n", foo(10, 5));
+
+    // a = -4, b = 3  -> expect 2
+    printf("%ld\n", foo(-4, 3));
+
+    // a = 9, b = 3  -> expect 1
+    printf("%ld\n", foo(9, 3));
+
+    return 0;
+}
@@ -0,0 +1,21 @@
+#include <stdio.h>
+#include <stdint.h>
+
+// Part 2: `!=` and conditions used as 0/1 values, packed as decimal digits
+// nb lt ne both
+// __asm("foo") is for macOS so the symbol name matches the assembly label `foo`
+extern long foo(long a, long b) __asm("foo");
+
+int main(void) {
+
+    // a = 1, b = 2  -> expect 1111
+    printf("%ld\n", foo(1, 2));
+
+    // a = 3, b = 2  -> expect 10
+    printf("%ld\n", foo(3, 2));
+
+    // a = 2, b = 2  -> expect 100
+    printf("%ld\n", foo(2, 2));
+
+    return 0;
+}
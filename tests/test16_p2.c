@@ -0,0 +1,21 @@
+#include <stdio.h>
+#include <stdint.h>
+
+// Part 2: bitwise operators and shifts; returns
+// ((a & 0xf0) | (b ^ 3)) + (~a >> b) + ((unsigned) a >> 60) + (1 << (b + 1))
+// __asm("foo") is for macOS so the symbol name matches the assembly label `foo`
+extern long foo(long a, long b) __asm("foo");
+
+int main(void) {
+
+    // a = 0x1234, b = 2  -> expect -1109
+    printf("%ld\n", foo(0x1234, 2));
+
+    // a = -8, b = 1  -> expect 264 (>>> fills with zeros, >> with the sign)
+    printf("%ld\n", foo(-8, 1));
+
+    // a = 255, b = 0  -> expect -11
+    printf("%ld\n", foo(255, 0));
+
+    return 0;
+}
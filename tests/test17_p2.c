@@ -0,0 +1,23 @@
+#include <stdio.h>
+#include <stdint.h>
+
+// Part 2: `else if` chains and `if` without `else`
+// __asm("foo") is for macOS so the symbol name matches the assembly label `foo`
+extern long foo(long a) __asm("foo");
+
+int main(void) {
+
+    // a = -3  -> expect -1
+    printf("%ld\n", foo(-3));
+
+    // a = 0  -> expect 0
+    printf("%ld\n", foo(0));
+
+    // a = 5  -> expect 101
+    printf("%ld\n", foo(5));
+
+    // a = 50  -> expect 2
+    printf("%ld\n", foo(50));
+
+    return 0;
+}
@@ -0,0 +1,20 @@
+#include <stdio.h>
+#include <stdint.h>
+
+// Part 2: `break` and `continue`; returns (sum of 1..n without multiples of 3) * 100 + (n + 1)
+// __asm("foo") is for macOS so the symbol name matches the assembly label `foo`
+extern long foo(long n) __asm("foo");
+
+int main(void) {
+
+    // n = 10  -> expect 3711
+    printf("%ld\n", foo(10));
+
+    // n = 0  -> expect 1
+    printf("%ld\n", foo(0));
+
+    // n = 4  -> expect 705
+    printf("%ld\n", foo(4));
+
+    return 0;
+}
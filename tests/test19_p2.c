@@ -0,0 +1,23 @@
+#include <stdio.h>
+#include <stdint.h>
+
+// Part 2: `for` and `do ... while`; returns ((sum of odd numbers below n) % 1000) * 10 + (digits of n)
+// __asm("foo") is for macOS so the symbol name matches the assembly label `foo`
+extern long foo(long n) __asm("foo");
+
+int main(void) {
+
+    // n = 10  -> expect 252
+    printf("%ld\n", foo(10));
+
+    // n = 0  -> expect 1
+    printf("%ld\n", foo(0));
+
+    // n = 7  -> expect 91
+    printf("%ld\n", foo(7));
+
+    // n = 100  -> expect 5003
+    printf("%ld\n", foo(100));
+
+    return 0;
+}
@@ -0,0 +1,27 @@
+#include <stdio.h>
+#include <stdint.h>
+
+// Part 2: `return EXPR;` anywhere; returns -1 for m == 0, (n < -100) for negative n,
+// otherwise the first multiple of m above n
+// __asm("foo") is for macOS so the symbol name matches the assembly label `foo`
+extern long foo(long n, long m) __asm("foo");
+
+int main(void) {
+
+    // n = 10, m = 3  -> expect 12
+    printf("%ld\n", foo(10, 3));
+
+    // n = 5, m = 0  -> expect -1
+    printf("%ld\n", foo(5, 0));
+
+    // n = -200, m = 4  -> expect 1
+    printf("%ld\n", foo(-200, 4));
+
+    // n = -5, m = 4  -> expect 0
+    printf("%ld\n", foo(-5, 4));
+
+    // n = 0, m = 7  -> expect 7
+    printf("%ld\n", foo(0, 7));
+
+    return 0;
+}
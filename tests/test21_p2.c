@@ -0,0 +1,20 @@
+#include <stdio.h>
+#include <stdint.h>
+
+// Part 2: compound assignments (`+=` ... `>>>=`) and `++` / `--`
+// __asm("foo") is for macOS so the symbol name matches the assembly label `foo`
+extern long foo(long n) __asm("foo");
+
+int main(void) {
+
+    // n = 5  -> expect 194
+    printf("%ld\n", foo(5));
+
+    // n = 0  -> expect 15
+    printf("%ld\n", foo(0));
+
+    // n = 20  -> expect 845
+    printf("%ld\n", foo(20));
+
+    return 0;
+}
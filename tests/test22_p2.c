@@ -0,0 +1,32 @@
+#include <stdio.h>
+#include <stdint.h>
+
+// Part 2: several `fn` definitions in one file, calls and recursion
+// __asm("...") is for macOS so the symbol names match the assembly labels
+extern long foo(long n, long m) __asm("foo");
+extern long fact(long n) __asm("fact");
+extern long fib(long n) __asm("fib");
+extern long deep(long n) __asm("deep");
+
+int main(void) {
+
+    // n = 5, m = 12  -> expect 144012120
+    printf("%ld\n", foo(5, 12));
+
+    // n = 3, m = 7  -> expect 13001006
+    printf("%ld\n", foo(3, 7));
+
+    // n = 0, m = 25  -> expect 5001001
+    printf("%ld\n", foo(0, 25));
+
+    // fact(10)  -> expect 3628800
+    printf("%ld\n", fact(10));
+
+    // fib(20)  -> expect 6765
+    printf("%ld\n", fib(20));
+
+    // 24 nested calls of inc  -> expect 124
+    printf("%ld\n", deep(100));
+
+    return 0;
+}
@@ -0,0 +1,22 @@
+#include <stdio.h>
+#include <stdint.h>
+
+// Part 2: more than six arguments, both received and passed
+// __asm("...") is for macOS so the symbol names match the assembly labels
+extern long foo(long x, long y) __asm("foo");
+extern long weigh(long a, long b, long c, long d, long e,
+                  long f, long g, long h, long i) __asm("weigh");
+
+int main(void) {
+
+    // x = 2, y = 3  -> expect 2000450
+    printf("%ld\n", foo(2, 3));
+
+    // x = 0, y = 1  -> expect 414
+    printf("%ld\n", foo(0, 1));
+
+    // weigh(1, 2, ..., 9)  -> expect 285
+    printf("%ld\n", weigh(1, 2, 3, 4, 5, 6, 7, 8, 9));
+
+    return 0;
+}
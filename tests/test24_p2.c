@@ -0,0 +1,23 @@
+#include <stdio.h>
+#include <stdint.h>
+
+// Part 2: calling C functions declared with `extern fn`
+// __asm("foo") is for macOS so the symbol name matches the assembly label `foo`
+extern long foo(long n) __asm("foo");
+
+// 2 * x if the caller kept %rsp 16-byte aligned at the `call`, -1000000 otherwise
+long checked(long x) {
+    uintptr_t frame = (uintptr_t)__builtin_frame_address(0);
+    return frame % 16 == 0 ? 2 * x : -1000000;
+}
+
+int main(void) {
+
+    // n = 3  -> expect a line `***`, then 6008
+    printf("%ld\n", foo(3));
+
+    // n = 0  -> expect an empty line, then 2
+    printf("%ld\n", foo(0));
+
+    return 0;
+}
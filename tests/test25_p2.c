@@ -0,0 +1,23 @@
+#include <stdio.h>
+
+// Part 2: `global` variables shared between calls and with C
+// __asm("...") is for macOS so the symbol names match the assembly labels
+extern long foo(long n) __asm("foo");
+extern long total(void) __asm("total");
+extern long counter __asm("counter");
+
+int main(void) {
+
+    // bumps by 3, 4  -> counter 7, expect 107 7
+    printf("%ld ", foo(2));
+    printf("%ld ", counter);
+
+    // counter persists: bumps by 5  -> 12, expect 112
+    printf("%ld ", foo(1));
+
+    // C writes it too: 40 + the global offset -7  -> expect 33
+    counter = 40;
+    printf("%ld\n", total());
+
+    return 0;
+}
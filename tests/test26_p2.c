@@ -0,0 +1,23 @@
+#include <stdio.h>
+
+// Part 2: local arrays with `int a[N];`, `a[i]` and `a[i] = e;`
+// __asm("...") is for macOS so the symbol names match the assembly labels
+extern long foo(long n) __asm("foo");
+extern long sorted_digits(long n) __asm("sorted_digits");
+
+int main(void) {
+
+    // fib(10) = 55, 15 primes below 50  -> expect 5515
+    printf("%ld\n", foo(10));
+
+    // fib(19) = 4181  -> expect 418115
+    printf("%ld\n", foo(19));
+
+    // expect 1234
+    printf("%ld\n", sorted_digits(3142));
+
+    // repeated digits; the 0 sorts first and drops out  -> expect 113579
+    printf("%ld\n", sorted_digits(9751301));
+
+    return 0;
+}
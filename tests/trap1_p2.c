@@ -0,0 +1,17 @@
+#include <stdio.h>
+
+// Part 2: --bounds-check aborts on an index past the end of an array
+// __asm("...") is for macOS so the symbol names match the assembly labels
+extern long element(long i) __asm("element");
+
+int main(void) {
+
+    // the last element  -> expect 40
+    printf("%ld\n", element(3));
+    fflush(stdout);
+
+    // one past the end  -> aborts with the message in trap1.expected
+    printf("%ld\n", element(4));
+
+    return 0;
+}
@@ -0,0 +1,18 @@
+#include <stdio.h>
+
+// Part 2: --bounds-check aborts on a negative index, which the check
+// sees as a huge unsigned one
+// __asm("...") is for macOS so the symbol names match the assembly labels
+extern long fill(long i) __asm("fill");
+
+int main(void) {
+
+    // element 0 is overwritten  -> expect 1
+    printf("%ld\n", fill(0));
+    fflush(stdout);
+
+    // a[-1]  -> aborts with the message in trap2.expected
+    printf("%ld\n", fill(-1));
+
+    return 0;
+}
@@ -0,0 +1,17 @@
+#include <stdio.h>
+
+// Part 2: division by zero aborts under the default --div-zero=trap
+// __asm("...") is for macOS so the symbol names match the assembly labels
+extern long ratio(long a, long b) __asm("ratio");
+
+int main(void) {
+
+    // expect -3
+    printf("%ld\n", ratio(-17, 5));
+    fflush(stdout);
+
+    // 7 / 0  -> aborts with the message in trap3.expected
+    printf("%ld\n", ratio(7, 0));
+
+    return 0;
+}
@@ -0,0 +1,17 @@
+#include <stdio.h>
+
+// Part 2: `%` by zero aborts under --div-zero=trap
+// __asm("...") is for macOS so the symbol names match the assembly labels
+extern long wrap(long a, long b) __asm("wrap");
+
+int main(void) {
+
+    // 1 + (-17 % 5)  -> expect -1
+    printf("%ld\n", wrap(-17, 5));
+    fflush(stdout);
+
+    // 7 % 0  -> aborts with the message in trap4.expected
+    printf("%ld\n", wrap(7, 0));
+
+    return 0;
+}
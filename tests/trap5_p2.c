@@ -0,0 +1,18 @@
+#include <stdio.h>
+
+// Part 2: --div-zero=undefined emits a bare `idivq`, so a zero divisor
+// raises SIGFPE instead of printing a message (trap5.expected is empty)
+// __asm("...") is for macOS so the symbol names match the assembly labels
+extern long ratio(long a, long b) __asm("ratio");
+
+int main(void) {
+
+    // expect -3
+    printf("%ld\n", ratio(-17, 5));
+    fflush(stdout);
+
+    // 7 / 0  -> killed by SIGFPE
+    printf("%ld\n", ratio(7, 0));
+
+    return 0;
+}